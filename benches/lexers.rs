@@ -0,0 +1,44 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 20;
+
+/// Best time of `ROUNDS` runs, to keep noise from other processes out.
+fn measure<F: FnMut() -> usize>(mut lex: F) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(lex());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, len: usize, time: Duration, baseline: Duration) {
+    let mb_per_s = len as f64 / time.as_secs_f64() / 1e6;
+    let speedup = baseline.as_secs_f64() / time.as_secs_f64();
+    println!("  {:<20} {:>9.2?} {:>8.1} MB/s {:>6.2}x", name, time, mb_per_s, speedup);
+}
+
+fn main() {
+    let fixtures = [
+        ("jquery-1.12.4.js", include_str!("../tests/jquery-1.12.4.js")),
+        ("jquery-1.12.4.min.js", include_str!("../tests/jquery-1.12.4.min.js")),
+    ];
+    for &(name, src) in &fixtures {
+        println!("{} ({} bytes)", name, src.len());
+        let chars = measure(|| tokenize_str(src).count());
+        let bytes = measure(|| tokenize_bytes(src.as_bytes()).unwrap().count());
+        let refs = measure(|| tokenize_str_ref(src).count());
+        let index = line_index::LineIndex::new(src);
+        let positions = measure(|| tokenize_str(src).with_positions(&index).count());
+        report("tokenize_str", src.len(), chars, chars);
+        report("tokenize_bytes", src.len(), bytes, chars);
+        report("tokenize_str_ref", src.len(), refs, chars);
+        report("with_positions", src.len(), positions, chars);
+    }
+}
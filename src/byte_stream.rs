@@ -0,0 +1,157 @@
+use std::convert::TryInto;
+use lex_stream::CharSource;
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// A `CharSource` over UTF-8 text in memory. ASCII is read a byte at a time, and runs of
+/// characters are skipped eight bytes at a time; only non-ASCII characters are decoded.
+/// The token text is a slice of the source, so nothing is copied.
+#[derive(Clone)]
+pub struct ByteStream<'a> {
+    src: &'a str,
+    pos: usize,
+    offset_utf16: usize,
+    token_start: usize,
+    reached_end: bool,
+}
+
+impl<'a> ByteStream<'a> {
+    pub fn new(src: &'a str) -> Self {
+        ByteStream {
+            src,
+            pos: 0,
+            offset_utf16: 0,
+            token_start: 0,
+            reached_end: false,
+        }
+    }
+
+    /// Same as `token_text`, but borrowed from the source rather than from the stream.
+    pub fn token_slice(&self) -> &'a str {
+        &self.src[self.token_start..self.pos]
+    }
+
+    /// Whether the end of the text was looked at since the last call, that is, whether
+    /// more text after it could have changed what was read.
+    pub fn take_reached_end(&mut self) -> bool {
+        std::mem::replace(&mut self.reached_end, false)
+    }
+
+    fn char_at(&mut self, pos: usize) -> Option<char> {
+        if pos >= self.src.len() {
+            self.reached_end = true;
+            return None;
+        }
+        match self.src.as_bytes()[pos] {
+            b if b < 0x80 => Some(b as char),
+            _ => self.src[pos..].chars().next(),
+        }
+    }
+
+    fn advance(&mut self, c: char) {
+        self.pos += c.len_utf8();
+        self.offset_utf16 += c.len_utf16();
+    }
+}
+
+/// Length of a UTF-8 sequence from its first byte.
+fn char_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7f => 1,
+        0x80..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+/// Sets the high bit of the first zero byte of `word`, and maybe of some bytes after it.
+fn zero_bytes(word: u64) -> u64 {
+    word.wrapping_sub(ONES) & !word & HIGH_BITS
+}
+
+impl<'a> CharSource for ByteStream<'a> {
+    fn read(&mut self) -> Option<char> {
+        let c = self.char_at(self.pos)?;
+        self.advance(c);
+        Some(c)
+    }
+
+    fn lookahead(&mut self, offset: usize) -> Option<char> {
+        let mut pos = self.pos;
+        for _ in 0..offset {
+            if pos >= self.src.len() {
+                self.reached_end = true;
+                return None;
+            }
+            pos += char_len(self.src.as_bytes()[pos]);
+        }
+        self.char_at(pos)
+    }
+
+    fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn offset_utf16(&self) -> usize {
+        self.offset_utf16
+    }
+
+    fn start_token(&mut self) {
+        self.token_start = self.pos;
+    }
+
+    fn token_text(&self) -> &str {
+        &self.src[self.token_start..self.pos]
+    }
+
+    fn skip_while<F: Fn(char) -> bool>(&mut self, predicate: F) {
+        let bytes = self.src.as_bytes();
+        while let Some(&b) = bytes.get(self.pos) {
+            if b < 0x80 {
+                if !predicate(b as char) {
+                    return;
+                }
+                self.pos += 1;
+                self.offset_utf16 += 1;
+            } else {
+                let c = self.char_at(self.pos).unwrap();
+                if !predicate(c) {
+                    return;
+                }
+                self.advance(c);
+            }
+        }
+        self.reached_end = true;
+    }
+
+    fn skip_ascii_until(&mut self, stop: &[u8]) {
+        let bytes = self.src.as_bytes();
+        let start = self.pos;
+        let mut pos = start;
+        while let Some(chunk) = bytes.get(pos..pos + 8) {
+            let word = u64::from_le_bytes(chunk.try_into().unwrap());
+            let mut found = word & HIGH_BITS;
+            for &b in stop {
+                found |= zero_bytes(word ^ (ONES * b as u64));
+            }
+            if found != 0 {
+                // the lowest flagged byte is always a real match
+                pos += found.trailing_zeros() as usize / 8;
+                self.offset_utf16 += pos - start;
+                self.pos = pos;
+                return;
+            }
+            pos += 8;
+        }
+        while let Some(&b) = bytes.get(pos) {
+            if b >= 0x80 || stop.contains(&b) {
+                break;
+            }
+            pos += 1;
+        }
+        self.reached_end |= pos == bytes.len();
+        self.offset_utf16 += pos - start;
+        self.pos = pos;
+    }
+}
@@ -0,0 +1,84 @@
+use Span;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    UnterminatedStringLiteral,
+    UnterminatedTemplateLiteral,
+    UnterminatedComment,
+    UnterminatedRegexp,
+    InvalidRegexpFlags,
+    InvalidHexEscape,
+    InvalidUnicodeEscape,
+    InvalidNumericLiteral,
+    UnexpectedCharacter,
+    LoneSurrogate,
+}
+
+impl DiagnosticCode {
+    /// A stable name for the code, e.g. `unterminated-string-literal`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiagnosticCode::UnterminatedStringLiteral => "unterminated-string-literal",
+            DiagnosticCode::UnterminatedTemplateLiteral => "unterminated-template-literal",
+            DiagnosticCode::UnterminatedComment => "unterminated-comment",
+            DiagnosticCode::UnterminatedRegexp => "unterminated-regexp",
+            DiagnosticCode::InvalidRegexpFlags => "invalid-regexp-flags",
+            DiagnosticCode::InvalidHexEscape => "invalid-hex-escape",
+            DiagnosticCode::InvalidUnicodeEscape => "invalid-unicode-escape",
+            DiagnosticCode::InvalidNumericLiteral => "invalid-numeric-literal",
+            DiagnosticCode::UnexpectedCharacter => "unexpected-character",
+            DiagnosticCode::LoneSurrogate => "lone-surrogate",
+        }
+    }
+
+    fn default_message(self) -> &'static str {
+        match self {
+            DiagnosticCode::UnterminatedStringLiteral => "unterminated string literal",
+            DiagnosticCode::UnterminatedTemplateLiteral => "unterminated template literal",
+            DiagnosticCode::UnterminatedComment => "unterminated comment",
+            DiagnosticCode::UnterminatedRegexp => "unterminated regular expression literal",
+            DiagnosticCode::InvalidRegexpFlags => "invalid regular expression flags",
+            DiagnosticCode::InvalidHexEscape => "invalid hexadecimal escape sequence",
+            DiagnosticCode::InvalidUnicodeEscape => "invalid Unicode escape sequence",
+            DiagnosticCode::InvalidNumericLiteral => "invalid numeric literal",
+            DiagnosticCode::UnexpectedCharacter => "unexpected character",
+            DiagnosticCode::LoneSurrogate => "lone surrogate outside of a string, comment or template",
+        }
+    }
+}
+
+/// A problem found by the lexer. The tokens are produced regardless, see `JsTokenIterator::diagnostics`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub span: Span,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    /// An error with the default message for `code`.
+    pub fn error(code: DiagnosticCode, span: Span) -> Diagnostic {
+        Diagnostic {
+            code,
+            message: code.default_message().to_owned(),
+            span,
+            severity: Severity::Error,
+        }
+    }
+}
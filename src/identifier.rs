@@ -0,0 +1,49 @@
+use std::borrow::Cow;
+use unicode_id_start::{is_id_continue, is_id_start};
+
+/// IdentifierStartChar: `$`, `_` or a character with the ID_Start property.
+pub fn is_identifier_start(c: char) -> bool {
+    c == '$' || c == '_' || is_id_start(c)
+}
+
+/// IdentifierPartChar: `$`, ZWNJ, ZWJ or a character with the ID_Continue property.
+pub fn is_identifier_part(c: char) -> bool {
+    c == '$' || c == '\u{200c}' || c == '\u{200d}' || is_id_continue(c)
+}
+
+/// Decodes `\uXXXX` and `\u{X...}` escapes in the raw text of an IdentifierName.
+/// Escapes that do not denote a valid code point are replaced with U+FFFD.
+pub fn decode_identifier_name(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        // consume_word only accepts well-formed escapes, so the `u` is always there
+        chars.next();
+        let mut digits = String::new();
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            for c in chars.by_ref() {
+                if c == '}' {
+                    break;
+                }
+                digits.push(c);
+            }
+        } else {
+            digits.extend(chars.by_ref().take(4));
+        }
+
+        let decoded = u32::from_str_radix(&digits, 16).ok().and_then(std::char::from_u32);
+        result.push(decoded.unwrap_or('\u{fffd}'));
+    }
+
+    Cow::Owned(result)
+}
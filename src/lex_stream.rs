@@ -1,44 +1,149 @@
+use std::collections::VecDeque;
+
+/// Where the lexer gets its characters from. `lookahead` and `read` are enough; the
+/// `skip_*` methods are there so that sources with random access can override them.
+pub trait CharSource {
+    fn read(&mut self) -> Option<char>;
+    
+    fn lookahead(&mut self, offset: usize) -> Option<char>;
+    
+    /// Number of UTF-8 bytes consumed so far.
+    fn offset(&self) -> usize;
+    
+    /// Number of UTF-16 code units consumed so far.
+    fn offset_utf16(&self) -> usize;
+    
+    /// Starts recording the characters read for a new token.
+    fn start_token(&mut self);
+    
+    /// Characters read since the last `start_token`.
+    fn token_text(&self) -> &str;
+    
+    fn skip(&mut self, count: usize) {
+        for _ in 0..count {
+            self.read();
+        }
+    }
+    
+    /// Reads characters as long as `predicate` holds.
+    fn skip_while<F: Fn(char) -> bool>(&mut self, predicate: F) {
+        while self.lookahead(0).is_some_and(&predicate) {
+            self.read();
+        }
+    }
+    
+    /// Reads ASCII characters up to the first one in `stop`. Stops at any non-ASCII character too.
+    fn skip_ascii_until(&mut self, stop: &[u8]) {
+        self.skip_while(|c| c.is_ascii() && !stop.contains(&(c as u8)));
+    }
+}
+
 pub struct LexStream<TIterator: Iterator<Item=char>> {
     iterator: TIterator,
-    lookahead_buf: Vec<char>,
+    // characters looked ahead, plus those read since the oldest open mark
+    buffer: VecDeque<char>,
+    // index in `buffer` of the next character; always 0 when no mark is open
+    cursor: usize,
+    open_marks: usize,
+    offset: usize,
+    offset_utf16: usize,
+    token_text: String,
+}
+
+/// A position to go back to with `LexStream::reset_to`, or to give up with `LexStream::release`.
+/// The characters read after it are kept in memory until then.
+#[must_use]
+#[derive(Debug)]
+pub struct Mark {
+    cursor: usize,
+    offset: usize,
+    offset_utf16: usize,
+    token_len: usize,
 }
 
 impl<TIterator: Iterator<Item=char>> LexStream<TIterator> {
     pub fn new(iterator: TIterator) -> Self {
         LexStream {
-            iterator: iterator,
-            lookahead_buf: Vec::new(),
+            iterator,
+            buffer: VecDeque::new(),
+            cursor: 0,
+            open_marks: 0,
+            offset: 0,
+            offset_utf16: 0,
+            token_text: String::new(),
         }
     }
     
-    pub fn read(&mut self) -> Option<char> {
-        if self.lookahead_buf.len() > 0 {
-            let c = self.lookahead_buf.remove(0);
-            Some(c)
-        } else {
-            let result = self.iterator.next();
-            result
+    /// The underlying character iterator.
+    pub fn get_mut(&mut self) -> &mut TIterator {
+        &mut self.iterator
+    }
+    
+    /// Remembers the current position, e.g. before a speculative lex.
+    pub fn mark(&mut self) -> Mark {
+        self.open_marks += 1;
+        Mark {
+            cursor: self.cursor,
+            offset: self.offset,
+            offset_utf16: self.offset_utf16,
+            token_len: self.token_text.len(),
         }
     }
     
-    pub fn skip(&mut self, count: usize) {
-        for _ in 0..count {
-            if self.lookahead_buf.len() > 0 {
-                self.lookahead_buf.remove(0);
-            } else {
-                self.iterator.next();
-            }
+    /// Goes back to `mark`, so that the characters read since are read again.
+    /// `token_text` is restored as well, as long as `start_token` was not called in between.
+    pub fn reset_to(&mut self, mark: Mark) {
+        self.cursor = mark.cursor;
+        self.offset = mark.offset;
+        self.offset_utf16 = mark.offset_utf16;
+        self.token_text.truncate(mark.token_len);
+        self.release(mark);
+    }
+    
+    /// Keeps the characters read since `mark` consumed.
+    pub fn release(&mut self, _mark: Mark) {
+        self.open_marks -= 1;
+        if self.open_marks == 0 {
+            self.buffer.drain(..self.cursor);
+            self.cursor = 0;
+        }
+    }
+}
+
+impl<TIterator: Iterator<Item=char>> CharSource for LexStream<TIterator> {
+    fn offset(&self) -> usize {
+        self.offset
+    }
+    
+    fn offset_utf16(&self) -> usize {
+        self.offset_utf16
+    }
+    
+    fn start_token(&mut self) {
+        self.token_text.clear();
+    }
+    
+    fn token_text(&self) -> &str {
+        &self.token_text
+    }
+    
+    fn read(&mut self) -> Option<char> {
+        let c = self.lookahead(0)?;
+        if self.open_marks == 0 {
+            self.buffer.pop_front();
+        } else {
+            self.cursor += 1;
         }
+        self.offset += c.len_utf8();
+        self.offset_utf16 += c.len_utf16();
+        self.token_text.push(c);
+        Some(c)
     }
     
-    pub fn lookahead(&mut self, offset: usize) -> Option<char> {
-        while self.lookahead_buf.len() < offset + 1 {
-            match self.iterator.next() {
-                Some(c) => self.lookahead_buf.push(c),
-                None => return None,
-            }
+    fn lookahead(&mut self, offset: usize) -> Option<char> {
+        while self.buffer.len() <= self.cursor + offset {
+            self.buffer.push_back(self.iterator.next()?);
         }
-        
-        Some(self.lookahead_buf[offset])
+        Some(self.buffer[self.cursor + offset])
     }
 }
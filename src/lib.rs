@@ -1,21 +1,256 @@
+extern crate num_bigint;
+extern crate unicode_id_start;
+
+use std::borrow::Cow;
 use std::vec::Vec;
+pub mod byte_stream;
+pub mod diagnostic;
+pub mod identifier;
 pub mod lex_stream;
-use lex_stream::LexStream;
+pub mod line_index;
+pub mod number_value;
+pub mod push;
+pub mod reader;
+pub mod regexp;
+pub mod render;
+pub mod string_value;
+pub mod typed;
+pub mod utf16;
+use byte_stream::ByteStream;
+use diagnostic::{Diagnostic, DiagnosticCode};
+use identifier::{decode_identifier_name, is_identifier_part, is_identifier_start};
+use lex_stream::{CharSource, LexStream};
+use reader::{InvalidUtf8, ReaderTokenIterator, Utf8Chars};
+use typed::{ContextualKeyword, Keyword, Punctuator};
+use utf16::{Utf16Chars, Utf16TokenIterator};
 
+/// A token with its source text.
+///
+/// The lexer is lossless: for any input, valid or not, joining the `source_text()` of every
+/// token reproduces the input exactly. Comments keep their delimiters; a `RegexpLiteral` holds
+/// the pattern and the flags, without the slashes.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum JsToken {
     Whitespace(String),
     LineTerminator(String),
+    /// `// ...`, without the line terminator.
     LineComment(String),
+    /// `/* ... */`, or up to the end of input if unterminated.
     MultilineComment(String),
+    Hashbang(String),
     Word(String),
+    PrivateName(String),
     StringLiteral(String),
     NumberLiteral(String),
     RegexpLiteral(String, String),
+    TemplateLiteral(String),
+    TemplateHead(String),
+    TemplateMiddle(String),
+    TemplateTail(String),
     Punctuation(String),
     Unknown(String),
 }
 
+/// Location of a token in the source, in UTF-8 bytes and in UTF-16 code units.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_utf16: usize,
+    pub end_utf16: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub kind: JsToken,
+    pub span: Span,
+}
+
+impl JsToken {
+    /// For a `Word`, the IdentifierName with unicode escapes decoded.
+    pub fn identifier_name(&self) -> Option<Cow<'_, str>> {
+        match *self {
+            JsToken::Word(ref raw) => Some(decode_identifier_name(raw)),
+            _ => None,
+        }
+    }
+
+    /// The text of the token as it appears in the source.
+    pub fn source_text(&self) -> Cow<'_, str> {
+        self.as_token_ref().source_text()
+    }
+
+    pub fn as_token_ref(&self) -> JsTokenRef<'_> {
+        match *self {
+            JsToken::Whitespace(ref x) => JsTokenRef::Whitespace(x),
+            JsToken::LineTerminator(ref x) => JsTokenRef::LineTerminator(x),
+            JsToken::LineComment(ref x) => JsTokenRef::LineComment(x),
+            JsToken::MultilineComment(ref x) => JsTokenRef::MultilineComment(x),
+            JsToken::Hashbang(ref x) => JsTokenRef::Hashbang(x),
+            JsToken::Word(ref x) => JsTokenRef::Word(x),
+            JsToken::PrivateName(ref x) => JsTokenRef::PrivateName(x),
+            JsToken::StringLiteral(ref x) => JsTokenRef::StringLiteral(x),
+            JsToken::NumberLiteral(ref x) => JsTokenRef::NumberLiteral(x),
+            JsToken::RegexpLiteral(ref x, ref flags) => JsTokenRef::RegexpLiteral(x, flags),
+            JsToken::TemplateLiteral(ref x) => JsTokenRef::TemplateLiteral(x),
+            JsToken::TemplateHead(ref x) => JsTokenRef::TemplateHead(x),
+            JsToken::TemplateMiddle(ref x) => JsTokenRef::TemplateMiddle(x),
+            JsToken::TemplateTail(ref x) => JsTokenRef::TemplateTail(x),
+            JsToken::Punctuation(ref x) => JsTokenRef::Punctuation(x),
+            JsToken::Unknown(ref x) => JsTokenRef::Unknown(x),
+        }
+    }
+}
+
+/// Same as `JsToken`, but borrowing the text from the source or the lexer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum JsTokenRef<'a> {
+    Whitespace(&'a str),
+    LineTerminator(&'a str),
+    LineComment(&'a str),
+    MultilineComment(&'a str),
+    Hashbang(&'a str),
+    Word(&'a str),
+    PrivateName(&'a str),
+    StringLiteral(&'a str),
+    NumberLiteral(&'a str),
+    RegexpLiteral(&'a str, &'a str),
+    TemplateLiteral(&'a str),
+    TemplateHead(&'a str),
+    TemplateMiddle(&'a str),
+    TemplateTail(&'a str),
+    Punctuation(&'a str),
+    Unknown(&'a str),
+}
+
+impl<'a> JsTokenRef<'a> {
+    /// For a `Word`, the IdentifierName with unicode escapes decoded.
+    /// Only allocates when the word contains escapes.
+    pub fn identifier_name(&self) -> Option<Cow<'a, str>> {
+        match *self {
+            JsTokenRef::Word(raw) => Some(decode_identifier_name(raw)),
+            _ => None,
+        }
+    }
+
+    /// The text of the token as it appears in the source. Only a `RegexpLiteral` needs
+    /// to allocate, to put its slashes back.
+    pub fn source_text(&self) -> Cow<'a, str> {
+        match *self {
+            JsTokenRef::RegexpLiteral(pattern, flags) => Cow::Owned(format!("/{}/{}", pattern, flags)),
+            JsTokenRef::Whitespace(x) |
+            JsTokenRef::LineTerminator(x) |
+            JsTokenRef::LineComment(x) |
+            JsTokenRef::MultilineComment(x) |
+            JsTokenRef::Hashbang(x) |
+            JsTokenRef::Word(x) |
+            JsTokenRef::PrivateName(x) |
+            JsTokenRef::StringLiteral(x) |
+            JsTokenRef::NumberLiteral(x) |
+            JsTokenRef::TemplateLiteral(x) |
+            JsTokenRef::TemplateHead(x) |
+            JsTokenRef::TemplateMiddle(x) |
+            JsTokenRef::TemplateTail(x) |
+            JsTokenRef::Punctuation(x) |
+            JsTokenRef::Unknown(x) => Cow::Borrowed(x),
+        }
+    }
+
+    fn from_raw(kind: RawKind, text: &'a str) -> Self {
+        match kind {
+            RawKind::Whitespace => JsTokenRef::Whitespace(text),
+            RawKind::LineTerminator => JsTokenRef::LineTerminator(text),
+            RawKind::LineComment => JsTokenRef::LineComment(text),
+            RawKind::MultilineComment => JsTokenRef::MultilineComment(text),
+            RawKind::Hashbang => JsTokenRef::Hashbang(text),
+            RawKind::Word => JsTokenRef::Word(text),
+            RawKind::PrivateName => JsTokenRef::PrivateName(text),
+            RawKind::StringLiteral => JsTokenRef::StringLiteral(text),
+            RawKind::NumberLiteral => JsTokenRef::NumberLiteral(text),
+            RawKind::RegexpLiteral { flags_start } => {
+                JsTokenRef::RegexpLiteral(&text[1..flags_start - 1], &text[flags_start..])
+            },
+            RawKind::TemplateLiteral => JsTokenRef::TemplateLiteral(text),
+            RawKind::TemplateHead => JsTokenRef::TemplateHead(text),
+            RawKind::TemplateMiddle => JsTokenRef::TemplateMiddle(text),
+            RawKind::TemplateTail => JsTokenRef::TemplateTail(text),
+            RawKind::Punctuation => JsTokenRef::Punctuation(text),
+            RawKind::Unknown => JsTokenRef::Unknown(text),
+        }
+    }
+}
+
+impl<'a> From<JsTokenRef<'a>> for JsToken {
+    fn from(token: JsTokenRef<'a>) -> JsToken {
+        match token {
+            JsTokenRef::Whitespace(x) => JsToken::Whitespace(x.to_owned()),
+            JsTokenRef::LineTerminator(x) => JsToken::LineTerminator(x.to_owned()),
+            JsTokenRef::LineComment(x) => JsToken::LineComment(x.to_owned()),
+            JsTokenRef::MultilineComment(x) => JsToken::MultilineComment(x.to_owned()),
+            JsTokenRef::Hashbang(x) => JsToken::Hashbang(x.to_owned()),
+            JsTokenRef::Word(x) => JsToken::Word(x.to_owned()),
+            JsTokenRef::PrivateName(x) => JsToken::PrivateName(x.to_owned()),
+            JsTokenRef::StringLiteral(x) => JsToken::StringLiteral(x.to_owned()),
+            JsTokenRef::NumberLiteral(x) => JsToken::NumberLiteral(x.to_owned()),
+            JsTokenRef::RegexpLiteral(x, flags) => JsToken::RegexpLiteral(x.to_owned(), flags.to_owned()),
+            JsTokenRef::TemplateLiteral(x) => JsToken::TemplateLiteral(x.to_owned()),
+            JsTokenRef::TemplateHead(x) => JsToken::TemplateHead(x.to_owned()),
+            JsTokenRef::TemplateMiddle(x) => JsToken::TemplateMiddle(x.to_owned()),
+            JsTokenRef::TemplateTail(x) => JsToken::TemplateTail(x.to_owned()),
+            JsTokenRef::Punctuation(x) => JsToken::Punctuation(x.to_owned()),
+            JsTokenRef::Unknown(x) => JsToken::Unknown(x.to_owned()),
+        }
+    }
+}
+
+/// What the lexer found, without the text. The text is the characters read for the token.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum RawKind {
+    Whitespace,
+    LineTerminator,
+    LineComment,
+    MultilineComment,
+    Hashbang,
+    Word,
+    PrivateName,
+    StringLiteral,
+    NumberLiteral,
+    // byte offset of the flags in the token text
+    RegexpLiteral { flags_start: usize },
+    TemplateLiteral,
+    TemplateHead,
+    TemplateMiddle,
+    TemplateTail,
+    Punctuation,
+    Unknown,
+}
+
+/// Checks that regular expression flags are known (`dgimsuvy`), are not repeated
+/// and do not combine `u` with `v`.
+pub fn is_valid_regexp_flags(flags: &str) -> bool {
+    let mut seen = String::new();
+    for c in flags.chars() {
+        if !"dgimsuvy".contains(c) || seen.contains(c) {
+            return false;
+        }
+        seen.push(c);
+    }
+    !(seen.contains('u') && seen.contains('v'))
+}
+
+/// LineTerminator: LF, CR, LS or PS.
+pub fn is_line_terminator(c: char) -> bool {
+    c == '\n' || c == '\r' || c == '\u{2028}' || c == '\u{2029}'
+}
+
+/// WhiteSpace: TAB, VT, FF, ZWNBSP or any Space_Separator (Zs).
+pub fn is_whitespace(c: char) -> bool {
+    matches!(c,
+        '\t' | '\u{000b}' | '\u{000c}' | '\u{feff}' |
+        ' ' | '\u{00a0}' | '\u{1680}' | '\u{2000}'..='\u{200a}' |
+        '\u{202f}' | '\u{205f}' | '\u{3000}')
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum FsmState {
     Initial,
@@ -23,35 +258,114 @@ enum FsmState {
     ExpectExpr,
 }
 
+/// State after a word that is not a property name.
+fn state_after_word(keyword: Option<Keyword>) -> FsmState {
+    match keyword {
+        // operators and statements that take an expression operand
+        Some(Keyword::Await) | Some(Keyword::Case) | Some(Keyword::Default) | Some(Keyword::Delete) |
+        Some(Keyword::Extends) | Some(Keyword::In) | Some(Keyword::Instanceof) | Some(Keyword::New) |
+        Some(Keyword::Return) | Some(Keyword::Throw) | Some(Keyword::Typeof) | Some(Keyword::Void) |
+        Some(Keyword::Yield) => FsmState::ExpectExpr,
+        // keywords that are followed by a statement
+        Some(Keyword::Debugger) | Some(Keyword::Do) | Some(Keyword::Else) | Some(Keyword::Finally) |
+        Some(Keyword::Try) => FsmState::Initial,
+        // identifiers, `this`, `super` and literals like `null`, `true`
+        _ => FsmState::AfterExpr,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ParenKind {
+    Expr,
+    // `if (...)`, `for (...)`, `while (...)`, `with (...)`
+    Statement,
+    // parameters of a function expression (true) or declaration (false)
+    FunctionParams(bool),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BraceKind {
+    Block,
+    ObjectLiteral,
+    FunctionBody,
+    ClassBody,
+    TemplateSubstitution,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Nesting {
+    Paren(ParenKind),
+    Bracket,
+    Brace(BraceKind),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct SavedState {
+    nesting: Nesting,
+    // state after the closing bracket
+    state: FsmState,
+    in_for: bool,
+    ternary_depth: usize,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 struct LexerVars {
     is_new_line: bool,
     last_token_disallows_newline: bool,
     last_token_nonexpr_paren: bool,
     last_token_for: bool,
+    last_token_arrow: bool,
+    last_token_dot: bool,
+    last_token_close_paren: Option<ParenKind>,
+    // state before `async`, so that `x = async function() {}` is seen as an expression
+    last_token_async: Option<FsmState>,
+    last_token_export: bool,
+    // `export default function () {}` and `export default class {}` are declarations
+    last_token_export_default: bool,
+}
+
+/// The lexer, over any source of characters; see `JsTokenIterator` and `JsTokenRefIterator`.
+pub struct Lexer<S: CharSource> {
+    char_iter: S,
+    state_stack: Vec<SavedState>,
+    state: FsmState,
+    in_for: bool,
+    ternary_depth: usize,
+    // `function` was seen and its parameter list is not opened yet; true for a function expression
+    pending_function: Option<bool>,
+    // `class` was seen and its body is not opened yet; true for a class expression
+    pending_class: Option<(bool, usize)>,
+    at_start: bool,
+    lexer_vars: LexerVars,
+    // byte and UTF-16 offsets where the current token starts
+    token_start: (usize, usize),
+    diagnostics: Vec<Diagnostic>,
 }
 
-pub struct JsTokenIterator<TIterator: Iterator<Item=char>> {
-    char_iter: LexStream<TIterator>,
-    state_stack: Vec<(char, FsmState, bool)>,
+/// The lexer state before a token, to lex it again; see `Lexer::checkpoint`.
+struct Checkpoint<S> {
+    char_iter: S,
+    // a token pushes or pops at most one entry
+    state_stack_len: usize,
+    state_stack_top: Option<SavedState>,
     state: FsmState,
     in_for: bool,
+    ternary_depth: usize,
+    pending_function: Option<bool>,
+    pending_class: Option<(bool, usize)>,
+    at_start: bool,
     lexer_vars: LexerVars,
+    diagnostics_len: usize,
 }
 
+/// Lexes characters from an iterator, buffering those looked ahead.
+pub type JsTokenIterator<TIterator> = Lexer<LexStream<TIterator>>;
+
+/// Lexes UTF-8 text in memory without decoding it to `char`s first; see `ByteStream`.
+type ByteTokenIterator<'a> = Lexer<ByteStream<'a>>;
+
 pub fn tokenize_chars<TIterator: Iterator<Item=char>>(src: TIterator) -> JsTokenIterator<TIterator> {
-    JsTokenIterator {
-        char_iter: LexStream::new(src),
-        state_stack: Vec::new(),
-        state: FsmState::Initial,
-        in_for: false,
-        lexer_vars: LexerVars {
-            is_new_line: true,
-            last_token_disallows_newline: false,
-            last_token_nonexpr_paren: false,
-            last_token_for: false,
-        },
-    }
+    Lexer::new(LexStream::new(src))
 }
 
 pub fn tokenize_str<'a>(src: &'a str) -> JsTokenIterator<std::str::Chars<'a>> {
@@ -59,341 +373,690 @@ pub fn tokenize_str<'a>(src: &'a str) -> JsTokenIterator<std::str::Chars<'a>> {
 }
 
 pub fn tokenize(src: &str) -> Vec<JsToken> {
-    let tokenizer = tokenize_str(src);
-    let result = tokenizer.collect();
-    
-    result
+    tokenize_str(src).collect()
+}
+
+pub fn tokenize_str_spanned(src: &str) -> SpannedTokenIterator<std::str::Chars<'_>> {
+    tokenize_str(src).spanned()
+}
+
+/// Tokenizes `src`, also returning the problems found in it.
+pub fn tokenize_with_diagnostics(src: &str) -> (Vec<Token>, Vec<Diagnostic>) {
+    let mut iter = tokenize_str_spanned(src);
+    let tokens = iter.by_ref().collect();
+    (tokens, iter.take_diagnostics())
+}
+
+/// Tokenizes UTF-8 bytes, giving the same tokens and diagnostics as `tokenize_str` but faster,
+/// as the tokens are borrowed from `src`; see `tokenize_str_ref`.
+pub fn tokenize_bytes(src: &[u8]) -> Result<JsTokenRefIterator<'_>, std::str::Utf8Error> {
+    Ok(tokenize_str_ref(std::str::from_utf8(src)?))
+}
+
+/// Tokenizes `src` without allocating a `String` per token; the tokens are slices of `src`.
+pub fn tokenize_str_ref(src: &str) -> JsTokenRefIterator<'_> {
+    JsTokenRefIterator {
+        inner: Lexer::new(ByteStream::new(src)),
+    }
+}
+
+/// Tokenizes UTF-8 text read from `reader`, holding no more than the current token in memory.
+/// Iteration stops after the first read error, or the first invalid UTF-8 with `InvalidUtf8::Error`.
+pub fn tokenize_reader<R: std::io::Read>(reader: R, on_invalid: InvalidUtf8) -> ReaderTokenIterator<R> {
+    ReaderTokenIterator::new(tokenize_chars(Utf8Chars::new(reader, on_invalid)))
+}
+
+/// Tokenizes UTF-16 source, which may contain lone surrogates; see `Utf16Token`.
+pub fn tokenize_utf16(src: &[u16]) -> Utf16TokenIterator<'_> {
+    Utf16TokenIterator::new(src, tokenize_chars(Utf16Chars::new(src)).spanned())
+}
+
+pub struct JsTokenRefIterator<'a> {
+    inner: ByteTokenIterator<'a>,
+}
+
+impl<'a> Iterator for JsTokenRefIterator<'a> {
+    type Item = JsTokenRef<'a>;
+    fn next(&mut self) -> Option<JsTokenRef<'a>> {
+        let kind = self.inner.next_raw()?;
+        Some(JsTokenRef::from_raw(kind, self.inner.char_iter.token_slice()))
+    }
+}
+
+impl<'a> JsTokenRefIterator<'a> {
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.inner.diagnostics()
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.inner.take_diagnostics()
+    }
+}
+
+pub struct SpannedTokenIterator<TIterator: Iterator<Item=char>> {
+    inner: JsTokenIterator<TIterator>,
+}
+
+impl<TIterator: Iterator<Item=char>> Iterator for SpannedTokenIterator<TIterator> {
+    type Item = Token;
+    fn next(&mut self) -> Option<Token> {
+        let start = self.inner.char_iter.offset();
+        let start_utf16 = self.inner.char_iter.offset_utf16();
+        let kind = self.inner.next()?;
+        let span = Span {
+            start,
+            end: self.inner.char_iter.offset(),
+            start_utf16,
+            end_utf16: self.inner.char_iter.offset_utf16(),
+        };
+        Some(Token { kind, span })
+    }
+}
+
+impl<TIterator: Iterator<Item=char>> SpannedTokenIterator<TIterator> {
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.inner.diagnostics()
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.inner.take_diagnostics()
+    }
 }
 
 impl<TIterator: Iterator<Item=char>> JsTokenIterator<TIterator> {
+    /// Turns this into an iterator over tokens with their spans.
+    pub fn spanned(self) -> SpannedTokenIterator<TIterator> {
+        SpannedTokenIterator { inner: self }
+    }
+}
+
+impl<S: CharSource> Lexer<S> {
+    fn new(char_iter: S) -> Self {
+        Lexer {
+            char_iter,
+            state_stack: Vec::new(),
+            state: FsmState::Initial,
+            in_for: false,
+            ternary_depth: 0,
+            pending_function: None,
+            pending_class: None,
+            at_start: true,
+            lexer_vars: LexerVars {
+                is_new_line: true,
+                last_token_disallows_newline: false,
+                last_token_nonexpr_paren: false,
+                last_token_for: false,
+                last_token_arrow: false,
+                last_token_dot: false,
+                last_token_close_paren: None,
+                last_token_async: None,
+                last_token_export: false,
+                last_token_export_default: false,
+            },
+            token_start: (0, 0),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Moves the lexer state over to another source, to go on with the next chunk of input.
+    fn with_source<T: CharSource>(self, char_iter: T) -> Lexer<T> {
+        Lexer {
+            char_iter,
+            state_stack: self.state_stack,
+            state: self.state,
+            in_for: self.in_for,
+            ternary_depth: self.ternary_depth,
+            pending_function: self.pending_function,
+            pending_class: self.pending_class,
+            at_start: self.at_start,
+            lexer_vars: self.lexer_vars,
+            token_start: self.token_start,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Saves the state before the next token, without copying the state stack or the diagnostics.
+    fn checkpoint(&self) -> Checkpoint<S> where S: Clone {
+        Checkpoint {
+            char_iter: self.char_iter.clone(),
+            state_stack_len: self.state_stack.len(),
+            state_stack_top: self.state_stack.last().cloned(),
+            state: self.state,
+            in_for: self.in_for,
+            ternary_depth: self.ternary_depth,
+            pending_function: self.pending_function,
+            pending_class: self.pending_class,
+            at_start: self.at_start,
+            lexer_vars: self.lexer_vars,
+            diagnostics_len: self.diagnostics.len(),
+        }
+    }
+
+    /// Goes back to `checkpoint`, which was taken before the last token.
+    fn rewind(&mut self, checkpoint: Checkpoint<S>) {
+        self.char_iter = checkpoint.char_iter;
+        self.state_stack.truncate(checkpoint.state_stack_len);
+        if self.state_stack.len() < checkpoint.state_stack_len {
+            self.state_stack.extend(checkpoint.state_stack_top);
+        }
+        self.state = checkpoint.state;
+        self.in_for = checkpoint.in_for;
+        self.ternary_depth = checkpoint.ternary_depth;
+        self.pending_function = checkpoint.pending_function;
+        self.pending_class = checkpoint.pending_class;
+        self.at_start = checkpoint.at_start;
+        self.lexer_vars = checkpoint.lexer_vars;
+        self.diagnostics.truncate(checkpoint.diagnostics_len);
+    }
+
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the diagnostics collected so far and clears them.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn offsets(&self) -> (usize, usize) {
+        (self.char_iter.offset(), self.char_iter.offset_utf16())
+    }
+
+    /// Reports an error from `start` to the current position.
+    fn report(&mut self, code: DiagnosticCode, start: (usize, usize)) -> &mut Diagnostic {
+        let span = Span {
+            start: start.0,
+            end: self.char_iter.offset(),
+            start_utf16: start.1,
+            end_utf16: self.char_iter.offset_utf16(),
+        };
+        self.diagnostics.push(Diagnostic::error(code, span));
+        self.diagnostics.last_mut().unwrap()
+    }
+
+    /// After an error token, forgets what the previous tokens implied about the next one,
+    /// so that a `/` is read as the start of a regexp again. Open brackets are kept.
+    fn resync(&mut self) {
+        self.state = FsmState::Initial;
+        self.pending_function = None;
+        self.pending_class = None;
+    }
+
+    fn push_state(&mut self, nesting: Nesting, after_state: FsmState) {
+        self.state_stack.push(SavedState {
+            nesting,
+            state: after_state,
+            in_for: self.in_for,
+            ternary_depth: self.ternary_depth,
+        });
+        self.in_for = false;
+        self.ternary_depth = 0;
+    }
+
+    fn pop_state(&mut self) -> SavedState {
+        let saved = self.state_stack.pop().unwrap_or(SavedState {
+            nesting: Nesting::Brace(BraceKind::Block),
+            state: FsmState::Initial,
+            in_for: false,
+            ternary_depth: 0,
+        });
+        self.state = saved.state;
+        self.in_for = saved.in_for;
+        self.ternary_depth = saved.ternary_depth;
+        saved
+    }
 
-    fn consume_number(&mut self) -> JsToken {
-        let mut r = String::new();
+    fn brace_kind(&mut self) -> (BraceKind, FsmState) {
+        if self.lexer_vars.last_token_arrow {
+            return (BraceKind::FunctionBody, FsmState::Initial);
+        }
+        if let Some((is_expr, depth)) = self.pending_class {
+            if depth == self.state_stack.len() {
+                self.pending_class = None;
+                let after_state = if is_expr { FsmState::AfterExpr } else { FsmState::Initial };
+                return (BraceKind::ClassBody, after_state);
+            }
+        }
+        match (self.lexer_vars.last_token_close_paren, self.state) {
+            (Some(ParenKind::FunctionParams(true)), _) => (BraceKind::FunctionBody, FsmState::AfterExpr),
+            (Some(ParenKind::FunctionParams(false)), _) => (BraceKind::FunctionBody, FsmState::Initial),
+            // method definition
+            (Some(ParenKind::Expr), _) => (BraceKind::FunctionBody, FsmState::Initial),
+            (Some(ParenKind::Statement), _) => (BraceKind::Block, FsmState::Initial),
+            (None, FsmState::ExpectExpr) => (BraceKind::ObjectLiteral, FsmState::AfterExpr),
+            (None, _) => (BraceKind::Block, FsmState::Initial),
+        }
+    }
+
+    fn is_statement_colon(&self) -> bool {
+        match self.state_stack.last() {
+            None => true,
+            Some(saved) => matches!(saved.nesting, Nesting::Brace(BraceKind::Block) | Nesting::Brace(BraceKind::FunctionBody)),
+        }
+    }
+
+    /// Returns the number of digits consumed.
+    fn consume_digits<F: Fn(char) -> bool>(&mut self, is_digit: F, allow_separators: bool) -> usize {
+        let mut count = 0;
+        let mut last_is_digit = false;
+        while let Some(c) = self.char_iter.lookahead(0) {
+            if is_digit(c) {
+                self.char_iter.skip(1);
+                count += 1;
+                last_is_digit = true;
+            } else if c == '_' && allow_separators && last_is_digit && self.char_iter.lookahead(1).is_some_and(&is_digit) {
+                self.char_iter.skip(1);
+                last_is_digit = false;
+            } else {
+                break;
+            }
+        }
+        count
+    }
+
+    fn consume_number(&mut self) -> RawKind {
         
         #[derive(Clone, Copy, Debug, Eq, PartialEq)]
         enum State {
-            Initial,
-            InitialZero,
-            Binary,
-            Octal,
             Decimal,
-            Hex,
+            LeadingDot,
+            LegacyOctal,
+            NonOctalDecimal,
+            Prefixed,
         }
         
-        let mut state = State::Initial;
+        // `0x` or `1e` without digits
+        let mut missing_digits = false;
         
-        while let Some(c) = self.char_iter.lookahead(0) {
-            //println!("consume_number: c = {:?}, state = {:?}", c, state);
-            match (c, state) {
-                ('0', State::Initial) => {
-                    r.push(c);
-                    self.char_iter.read();
-                    state = State::InitialZero;
-                },
-                ('1'...'9', State::Initial) => {
-                    r.push(c);
-                    self.char_iter.read();
-                    state = State::Decimal;
-                },
-                (_, State::Initial) => {
-                    break;
-                },
-                ('b', State::InitialZero) |
-                ('B', State::InitialZero) => {
-                    r.push(c);
-                    self.char_iter.read();
-                    state = State::Binary;
-                },
-                ('o', State::InitialZero) |
-                ('O', State::InitialZero) => {
-                    r.push(c);
-                    self.char_iter.read();
-                    state = State::Octal;
-                },
-                ('x', State::InitialZero) |
-                ('X', State::InitialZero) => {
-                    r.push(c);
-                    self.char_iter.read();
-                    state = State::Hex;
-                },
-                ('0'...'1', State::Binary) |
-                ('0'...'7', State::Octal) |
-                ('0'...'9', State::InitialZero) |
-                ('0'...'9', State::Decimal) |
-                ('0'...'9', State::Hex)|
-                ('a'...'f', State::Hex)|
-                ('A'...'F', State::Hex) => {
-                    r.push(c);
-                    self.char_iter.read();
-                },
-                (_, _) => {
-                    break;
+        let state = match (self.char_iter.lookahead(0), self.char_iter.lookahead(1)) {
+            (Some('0'), Some('b')) | (Some('0'), Some('B')) => {
+                self.char_iter.skip(2);
+                missing_digits = self.consume_digits(|c| c == '0' || c == '1', true) == 0;
+                State::Prefixed
+            },
+            (Some('0'), Some('o')) | (Some('0'), Some('O')) => {
+                self.char_iter.skip(2);
+                missing_digits = self.consume_digits(|c| ('0'..='7').contains(&c), true) == 0;
+                State::Prefixed
+            },
+            (Some('0'), Some('x')) | (Some('0'), Some('X')) => {
+                self.char_iter.skip(2);
+                missing_digits = self.consume_digits(|c| c.is_ascii_hexdigit(), true) == 0;
+                State::Prefixed
+            },
+            (Some('0'), Some('0'..='9')) => {
+                // legacy octal (`017`) or non-octal decimal (`089`), no separators allowed
+                self.consume_digits(|c| c.is_ascii_digit(), false);
+                if self.char_iter.token_text().chars().all(|c| ('0'..='7').contains(&c)) {
+                    State::LegacyOctal
+                } else {
+                    State::NonOctalDecimal
                 }
-            }
-        }
+            },
+            (Some('0'), _) => {
+                self.char_iter.skip(1);
+                State::Decimal
+            },
+            (Some('.'), _) => {
+                self.char_iter.skip(1);
+                self.consume_digits(|c| c.is_ascii_digit(), true);
+                State::LeadingDot
+            },
+            (_, _) => {
+                self.consume_digits(|c| c.is_ascii_digit(), true);
+                State::Decimal
+            },
+        };
         
+        let mut is_integer = state != State::LeadingDot;
         
-        if state == State::Decimal {
-            if Some('.') == self.char_iter.lookahead(0) {
-                r.push('.');
+        if (state == State::Decimal || state == State::NonOctalDecimal) && Some('.') == self.char_iter.lookahead(0) {
+            self.char_iter.skip(1);
+            self.consume_digits(|c| c.is_ascii_digit(), true);
+            is_integer = false;
+        }
+        
+        if state == State::Decimal || state == State::NonOctalDecimal || state == State::LeadingDot {
+            if let Some('e') | Some('E') = self.char_iter.lookahead(0) {
                 self.char_iter.skip(1);
-                while let Some(c) = self.char_iter.lookahead(0) {
-                    match c {
-                        '0'...'9' => {
-                            r.push(c);
-                            self.char_iter.skip(1);
-                        },
-                        _ => {
-                            break;
-                        },
-                    }
-                }
-            }
-            
-            match self.char_iter.lookahead(0) {
-                Some(c @ 'e') | Some(c @ 'E') => {
-                    r.push(c);
+                
+                if let Some('-') | Some('+') = self.char_iter.lookahead(0) {
                     self.char_iter.skip(1);
-                    
-                    match self.char_iter.lookahead(0) {
-                        Some('-') => {
-                            r.push('-');
-                            self.char_iter.skip(1);
-                        },
-                        _ => { }
-                    }
-                    
-                    while let Some(c) = self.char_iter.lookahead(0) {
-                        match c {
-                            '0'...'9' => {
-                                r.push(c);
-                                self.char_iter.skip(1);
-                            },
-                            _ => {
-                                break;
-                            }
-                        }
-                    }
-                },
-                _ => { }
+                }
+                
+                missing_digits = self.consume_digits(|c| c.is_ascii_digit(), true) == 0;
+                is_integer = false;
             }
         }
         
-        JsToken::NumberLiteral(r)
+        if is_integer && (state == State::Decimal || state == State::Prefixed) && Some('n') == self.char_iter.lookahead(0) {
+            self.char_iter.skip(1);
+        }
+        
+        let start = self.token_start;
+        if missing_digits {
+            self.report(DiagnosticCode::InvalidNumericLiteral, start);
+        } else if self.char_iter.lookahead(0).is_some_and(|c| c.is_ascii_digit() || is_identifier_start(c)) || self.lookahead_identifier_escape(0).is_some() {
+            // `3in`, `0b12`, `1_000_`
+            self.report(DiagnosticCode::InvalidNumericLiteral, start).message =
+                "identifier or digit immediately after numeric literal".to_owned();
+        }
+        
+        RawKind::NumberLiteral
     }
 
-    fn consume_whitespace(&mut self) -> JsToken {
-        let mut result = String::new();
-        while let Some(c) = self.char_iter.lookahead(0) {
-            match c {
-                ' '|'\t'|'\u{000b}'|'\u{000c}'|'\u{00a0}' => {
-                    result.push(c);
-                    self.char_iter.read();
+    fn consume_whitespace(&mut self) -> RawKind {
+        self.char_iter.skip_while(is_whitespace);
+        RawKind::Whitespace
+    }
+
+    fn consume_string_literal(&mut self) -> RawKind {
+        let quote = self.char_iter.read().unwrap();
+        let stop = [quote as u8, b'\\', b'\n', b'\r'];
+        
+        loop {
+            self.char_iter.skip_ascii_until(&stop);
+            let c = match self.char_iter.lookahead(0) {
+                Some(c) => c,
+                None => {
+                    let start = self.token_start;
+                    self.report(DiagnosticCode::UnterminatedStringLiteral, start);
+                    break;
                 },
-                _ => break,
+            };
+            if c == '\n' || c == '\r' {
+                // U+2028 and U+2029 are allowed, but LF and CR end a string missing its quote,
+                // so that the lines below still lex normally
+                let start = self.token_start;
+                self.report(DiagnosticCode::UnterminatedStringLiteral, start);
+                self.resync();
+                break;
+            }
+            let start = self.offsets();
+            self.char_iter.read();
+            match c {
+                _ if c == quote => break,
+                '\\' => self.consume_string_escape(start),
+                _ => {},
             }
         }
-        JsToken::Whitespace(result)
+        RawKind::StringLiteral
     }
 
-    fn consume_string_literal(&mut self) -> JsToken {
-        let mut r = String::new();
-        
-        #[derive(Copy, Clone)]
-        enum QuoteKind {
-            Single,
-            Double,
-        }
-        
-        let quote = match self.char_iter.read().unwrap() {
-            '\'' => {
-                r.push('\'');
-                QuoteKind::Single
-            },
-            '\"' => {
-                r.push('\"');
-                QuoteKind::Double
+    /// Consumes the part of an escape sequence after the `\\`, reporting malformed `\x` and `\u` escapes.
+    fn consume_string_escape(&mut self, start: (usize, usize)) {
+        match self.char_iter.read() {
+            Some('x') => {
+                let count = self.consume_hex_digits(2);
+                if count < 2 {
+                    self.report(DiagnosticCode::InvalidHexEscape, start);
+                }
             },
-            _ => unreachable!(),
-        };
-        
-        #[derive(Copy, Clone)]
-        enum State {
-            Initial,
-            Backslash,
-        }
-        
-        let mut state = State::Initial;
-        
-        while let Some(c) = self.char_iter.lookahead(0) {
-            match (c, state, quote) {
-                ('\'', State::Initial, QuoteKind::Single) |
-                ('\"', State::Initial, QuoteKind::Double) => {
+            Some('u') if self.char_iter.lookahead(0) == Some('{') => {
+                self.char_iter.read();
+                let mut value = 0u32;
+                let mut count = 0;
+                while let Some(digit) = self.char_iter.lookahead(0).and_then(|c| c.to_digit(16)) {
                     self.char_iter.read();
-                    r.push(c);
-                    break;
-                },
-                ('x', State::Backslash, _) => {
+                    value = value.saturating_mul(16).saturating_add(digit);
+                    count += 1;
+                }
+                if count > 0 && value <= 0x10ffff && self.char_iter.lookahead(0) == Some('}') {
                     self.char_iter.read();
-                    r.push(c);
-                    for _ in 0..2 {
-                        if let Some(c) = self.char_iter.read() {
-                            r.push(c);
-                        }
-                    }
-                    state = State::Initial;
+                } else {
+                    self.report(DiagnosticCode::InvalidUnicodeEscape, start);
+                }
+            },
+            Some('u') => {
+                let count = self.consume_hex_digits(4);
+                if count < 4 {
+                    self.report(DiagnosticCode::InvalidUnicodeEscape, start);
+                }
+            },
+            Some('\r') if self.char_iter.lookahead(0) == Some('\n') => {
+                self.char_iter.read();
+            },
+            // the unterminated literal is reported by the caller
+            _ => {},
+        }
+    }
+
+    fn consume_hex_digits(&mut self, max_count: usize) -> usize {
+        let mut count = 0;
+        while count < max_count && self.char_iter.lookahead(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.char_iter.read();
+            count += 1;
+        }
+        count
+    }
+
+    fn consume_template(&mut self) -> RawKind {
+        let opening = self.char_iter.read().unwrap();
+
+        let mut has_substitution = false;
+        let mut found_end = false;
+
+        loop {
+            self.char_iter.skip_ascii_until(b"\\`$");
+            let c = match self.char_iter.lookahead(0) {
+                Some(c) => c,
+                None => break,
+            };
+            match (c, self.char_iter.lookahead(1)) {
+                ('\\', Some(_)) => {
+                    self.char_iter.skip(2);
                 },
-                ('u', State::Backslash, _) => {
-                    self.char_iter.read();
-                    r.push(c);
-                    if Some('{') == self.char_iter.lookahead(0) {
-                        r.push(self.char_iter.read().unwrap());
-                        while let Some(c) = self.char_iter.read() {
-                            r.push(c);
-                            if c == '}' {
-                                break;
-                            }
-                        }
-                    } else {
-                        for _ in 0..4 {
-                            if let Some(c) = self.char_iter.read() {
-                                r.push(c);
-                            }
-                        }
-                    }
-                    state = State::Initial;
+                ('`', _) => {
+                    self.char_iter.skip(1);
+                    found_end = true;
+                    break;
                 },
-                ('\\', State::Initial, _) => {
-                    self.char_iter.read();
-                    r.push(c);
-                    state = State::Backslash;
+                ('$', Some('{')) => {
+                    self.char_iter.skip(2);
+                    has_substitution = true;
+                    found_end = true;
+                    break;
                 },
-                (_, _, _) => {
-                    self.char_iter.read();
-                    r.push(c);
-                    state = State::Initial;
+                (_, _) => {
+                    self.char_iter.skip(1);
                 },
             }
         }
-        JsToken::StringLiteral(r)
+        if !found_end {
+            let start = self.token_start;
+            self.report(DiagnosticCode::UnterminatedTemplateLiteral, start);
+        }
+
+        match (opening, has_substitution) {
+            ('`', false) => RawKind::TemplateLiteral,
+            ('`', true) => RawKind::TemplateHead,
+            (_, true) => RawKind::TemplateMiddle,
+            (_, false) => RawKind::TemplateTail,
+        }
     }
 
-    fn consume_line_terminator(&mut self) -> JsToken {
-        let mut result = String::new();
-        while let Some(c) = self.char_iter.lookahead(0) {
-            match c {
-                '\r'|'\n' => {
-                    result.push(c);
-                    self.char_iter.read();
-                },
-                _ => break,
+    fn consume_line_terminator(&mut self) -> RawKind {
+        let c = self.char_iter.read().unwrap();
+        if c == '\r' && self.char_iter.lookahead(0) == Some('\n') {
+            self.char_iter.read();
+        }
+        RawKind::LineTerminator
+    }
+
+    fn lookahead_identifier_escape(&mut self, offset: usize) -> Option<usize> {
+        if self.char_iter.lookahead(offset) != Some('\\') || self.char_iter.lookahead(offset + 1) != Some('u') {
+            return None;
+        }
+        if self.char_iter.lookahead(offset + 2) == Some('{') {
+            let mut len = 3;
+            while self.char_iter.lookahead(offset + len).is_some_and(|c| c.is_ascii_hexdigit()) {
+                len += 1;
+            }
+            if len > 3 && self.char_iter.lookahead(offset + len) == Some('}') {
+                Some(len + 1)
+            } else {
+                None
             }
+        } else {
+            for i in 2..6 {
+                if !self.char_iter.lookahead(offset + i).is_some_and(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+            }
+            Some(6)
         }
-        JsToken::LineTerminator(result)
     }
 
-    fn consume_word(&mut self) -> JsToken {
-        let mut result = String::new();
+    fn consume_word(&mut self) -> RawKind {
+        let mut is_start = true;
         while let Some(c) = self.char_iter.lookahead(0) {
-            if c == '_' || c == '$' || c.is_alphanumeric() {
-                result.push(c);
+            if let Some(len) = self.lookahead_identifier_escape(0) {
+                let start = self.offsets();
+                self.char_iter.skip(len);
+                // `\u0030abc` or `a\u{2F}`: the escape must itself be a valid identifier character
+                let text = self.char_iter.token_text();
+                let escaped = decode_identifier_name(&text[text.len() - len..]).chars().next();
+                let is_valid = match escaped {
+                    Some(c) if is_start => is_identifier_start(c),
+                    Some(c) => is_identifier_part(c),
+                    None => false,
+                };
+                if !is_valid {
+                    self.report(DiagnosticCode::InvalidUnicodeEscape, start);
+                }
+            } else if (is_start && is_identifier_start(c)) || (!is_start && is_identifier_part(c)) {
                 self.char_iter.read();
+                self.char_iter.skip_while(is_identifier_part);
             } else {
                 break;
             }
+            is_start = false;
+        }
+        RawKind::Word
+    }
+
+    fn consume_private_name(&mut self) -> RawKind {
+        self.char_iter.read();
+        self.consume_word();
+        RawKind::PrivateName
+    }
+
+    fn skip_to_line_terminator(&mut self) {
+        loop {
+            self.char_iter.skip_ascii_until(b"\n\r");
+            match self.char_iter.lookahead(0) {
+                Some(c) if !is_line_terminator(c) => {
+                    self.char_iter.read();
+                },
+                _ => break,
+            }
         }
-        JsToken::Word(result)
     }
 
-    fn consume_regexp(&mut self) -> JsToken {
-        let mut result = String::new();
-        let mut flags = String::new();
-        result.push(self.char_iter.read().unwrap());
+    fn consume_hashbang(&mut self) -> RawKind {
+        self.skip_to_line_terminator();
+        RawKind::Hashbang
+    }
+
+    fn consume_regexp(&mut self) -> RawKind {
+        let mut in_class = false;
         let mut found_end = false;
         while let Some(c) = self.char_iter.lookahead(0) {
-            match (c, self.char_iter.lookahead(1)) {
-                ('\\', Some(c2)) => {
+            match (c, self.char_iter.lookahead(1), in_class) {
+                (c, _, _) if is_line_terminator(c) => {
+                    break;
+                },
+                ('\\', Some(c2), _) if !is_line_terminator(c2) => {
                     self.char_iter.skip(2);
-                    result.push('\\');
-                    result.push(c2);
                 },
-                ('/', _) => {
+                ('\\', _, _) => {
+                    self.char_iter.skip(1);
+                    break;
+                },
+                ('[', _, false) => {
+                    in_class = true;
+                    self.char_iter.skip(1);
+                },
+                (']', _, true) => {
+                    in_class = false;
+                    self.char_iter.skip(1);
+                },
+                ('/', _, false) => {
                     found_end = true;
                     self.char_iter.skip(1);
                     break;
                 },
                 _ => {
-                    result.push(c);
                     self.char_iter.skip(1);
                 },
             }
         }
-        if found_end {
-            while let Some(c) = self.char_iter.lookahead(0) {
-                match c {
-                    'a'...'z' => {
-                        flags.push(c);
-                        self.char_iter.skip(1);
-                    },
-                    _ => {
-                        break;
-                    },
-                }
-            }
+        if !found_end {
+            let start = self.token_start;
+            self.report(DiagnosticCode::UnterminatedRegexp, start);
+            self.resync();
+            return RawKind::Unknown;
         }
-        JsToken::RegexpLiteral(result, flags)
-    }
-
-    fn consume_line_comment(&mut self) -> JsToken {
-        self.char_iter.read();
-        self.char_iter.read();
-        let mut result = String::new();
-        result.push(self.char_iter.read().unwrap());
+        let flags_start = self.char_iter.token_text().len();
+        let flags_position = self.offsets();
         while let Some(c) = self.char_iter.lookahead(0) {
-            if c == '\n' || c == '\r' {
-                break;
+            if is_identifier_part(c) {
+                self.char_iter.skip(1);
             } else {
-                result.push(c);
-                self.char_iter.read();
+                break;
             }
         }
-        JsToken::LineComment(result)
+        if !is_valid_regexp_flags(&self.char_iter.token_text()[flags_start..]) {
+            self.report(DiagnosticCode::InvalidRegexpFlags, flags_position);
+        }
+        RawKind::RegexpLiteral { flags_start }
     }
 
-    fn consume_multiline_comment(&mut self) -> JsToken {
-        self.char_iter.read();
-        self.char_iter.read();
-        let mut result = String::new();
-        result.push(self.char_iter.read().unwrap());
-        
-        while let Some(c) = self.char_iter.lookahead(0) {
-            match c {
-                '*' => {
-                    match self.char_iter.lookahead(1) {
-                        Some('/') => {
-                            self.char_iter.read();
-                            self.char_iter.read();
-                            break;
-                        },
-                        _ => {
-                            result.push(c);
-                            self.char_iter.read();
-                        }
-                    }
-                }
-                _ => {
-                    result.push(c);
+    fn consume_line_comment(&mut self) -> RawKind {
+        self.char_iter.skip(2);
+        self.skip_to_line_terminator();
+        RawKind::LineComment
+    }
+
+    fn consume_multiline_comment(&mut self) -> RawKind {
+        self.char_iter.skip(2);
+        loop {
+            self.char_iter.skip_ascii_until(b"*");
+            match self.char_iter.lookahead(0) {
+                Some('*') if self.char_iter.lookahead(1) == Some('/') => {
+                    self.char_iter.skip(2);
+                    break;
+                },
+                Some(_) => {
                     self.char_iter.read();
-                }
+                },
+                None => {
+                    let start = self.token_start;
+                    self.report(DiagnosticCode::UnterminatedComment, start);
+                    break;
+                },
             }
         }
-        JsToken::MultilineComment(result)
+        RawKind::MultilineComment
     }
 }
 
-impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
+impl<S: CharSource> Iterator for Lexer<S> {
     type Item = JsToken;
     fn next(&mut self) -> Option<JsToken> {
+        let kind = self.next_raw()?;
+        Some(JsTokenRef::from_raw(kind, self.char_iter.token_text()).into())
+    }
+}
+
+impl<S: CharSource> Lexer<S> {
+    /// Lexes the next token; its text is left in `char_iter.token_text()`.
+    fn next_raw(&mut self) -> Option<RawKind> {
+        self.char_iter.start_token();
+        self.token_start = self.offsets();
         match self.char_iter.lookahead(0) {
             None => None,
             Some(c) => {
@@ -402,98 +1065,136 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                 //    "tokenize: c = {:?}, state = {:?}, in_for = {:?}, last_token_nonexpr_paren = {:?}",
                 //    c, state, in_for,
                 //    lexer_vars.last_token_nonexpr_paren);
+                let prev_state = self.state;
+                let mut last_closed_paren = None;
+                // of a word that is not a property name
+                let mut keyword = None;
+                let mut contextual = None;
                 let token;
                 match c {
-                    '0'...'9' => {
+                    '0'..='9' => {
                         token = self.consume_number();
                         self.state = FsmState::AfterExpr;
                     },
-                    ' '|'\t'|'\u{000b}'|'\u{000c}'|'\u{00a0}' => token = self.consume_whitespace(),
-                    '\r'|'\n' => token = self.consume_line_terminator(),
-                    '\''|'"' => token = self.consume_string_literal(),
-                    _ if c == '_' || c == '$' || c.is_alphabetic() => {
-                        let word = self.consume_word();
-                        if let JsToken::Word(ref w) = word {
-                            if w == "return" || w == "yield" {
-                                self.state = FsmState::ExpectExpr;
-                            } else {
-                                self.state = FsmState::AfterExpr;
+                    _ if is_whitespace(c) => token = self.consume_whitespace(),
+                    _ if is_line_terminator(c) => token = self.consume_line_terminator(),
+                    '\''|'"' => {
+                        // set first, as a string missing its quote resyncs the lexer
+                        self.state = FsmState::AfterExpr;
+                        token = self.consume_string_literal();
+                    },
+                    _ if is_identifier_start(c) || self.lookahead_identifier_escape(0).is_some() => {
+                        token = self.consume_word();
+                        if !self.lexer_vars.last_token_dot {
+                            // decode once, and skip the tables for words that cannot be in them
+                            let name = decode_identifier_name(self.char_iter.token_text());
+                            if name.len() <= 10 && name.bytes().all(|b| b.is_ascii_lowercase()) {
+                                keyword = Keyword::from_name(&name);
+                                contextual = if keyword.is_none() { ContextualKeyword::from_name(&name) } else { None };
                             }
-                        } else {
-                            self.state = FsmState::AfterExpr;
                         }
-                        
-                        token = word;
+                        let state_before = if self.lexer_vars.last_token_export_default { FsmState::Initial } else { self.state };
+                        if keyword == Some(Keyword::Function) {
+                            let state_before = self.lexer_vars.last_token_async.unwrap_or(state_before);
+                            self.pending_function = Some(state_before == FsmState::ExpectExpr);
+                        } else if keyword == Some(Keyword::Class) {
+                            self.pending_class = Some((state_before == FsmState::ExpectExpr, self.state_stack.len()));
+                        }
+                        // `of` after the binding in a for head takes an expression, as in `for (x of /re/.exec(s))`;
+                        // elsewhere it is an identifier
+                        let is_for_of = contextual == Some(ContextualKeyword::Of) && self.in_for && self.state == FsmState::AfterExpr;
+                        self.state = if is_for_of { FsmState::ExpectExpr } else { state_after_word(keyword) };
                     },
                     '(' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        let after_state = match (self.state, self.lexer_vars.last_token_nonexpr_paren) {
-                            (_, true) => FsmState::Initial,
-                            (FsmState::Initial, _) => FsmState::AfterExpr,
-                            (FsmState::AfterExpr, _) => FsmState::AfterExpr,
-                            (FsmState::ExpectExpr, _) => FsmState::AfterExpr,
+                        token = RawKind::Punctuation;
+                        let kind = match (self.pending_function.take(), self.lexer_vars.last_token_nonexpr_paren) {
+                            (Some(is_expr), _) => ParenKind::FunctionParams(is_expr),
+                            (None, true) => ParenKind::Statement,
+                            (None, false) => ParenKind::Expr,
+                        };
+                        let after_state = match kind {
+                            ParenKind::Statement => FsmState::Initial,
+                            _ => FsmState::AfterExpr,
                         };
-                        self.state_stack.push((')', after_state, self.in_for));
-                        self.in_for = if self.lexer_vars.last_token_for { true } else { false };
+                        self.push_state(Nesting::Paren(kind), after_state);
+                        self.in_for = self.lexer_vars.last_token_for;
                         self.state = FsmState::ExpectExpr;
                     },
                     ')' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        let (_, st1, sc) = self.state_stack.pop().unwrap_or(('(', FsmState::Initial, false));
-                        self.state = st1;
-                        self.in_for = sc;
+                        token = RawKind::Punctuation;
+                        if let Nesting::Paren(kind) = self.pop_state().nesting {
+                            last_closed_paren = Some(kind);
+                        }
                     },
                     '{' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        let after_state = match self.state {
-                            FsmState::Initial => FsmState::Initial,
-                            FsmState::AfterExpr => FsmState::Initial,
-                            FsmState::ExpectExpr => FsmState::AfterExpr,
+                        token = RawKind::Punctuation;
+                        let (kind, after_state) = self.brace_kind();
+                        self.push_state(Nesting::Brace(kind), after_state);
+                        self.state = match kind {
+                            BraceKind::ObjectLiteral => FsmState::ExpectExpr,
+                            _ => FsmState::Initial,
                         };
-                        self.state_stack.push(('}', after_state, self.in_for));
-                        self.in_for = false;
-                        self.state = FsmState::Initial;
+                    },
+                    '#' if self.at_start && self.char_iter.lookahead(1) == Some('!') => {
+                        token = self.consume_hashbang();
+                    },
+                    '#' if self.char_iter.lookahead(1).is_some_and(is_identifier_start) || self.lookahead_identifier_escape(1).is_some() => {
+                        token = self.consume_private_name();
+                        self.state = FsmState::AfterExpr;
+                    },
+                    '`' => {
+                        token = self.consume_template();
+                        if token == RawKind::TemplateHead {
+                            self.push_state(Nesting::Brace(BraceKind::TemplateSubstitution), FsmState::AfterExpr);
+                            self.state = FsmState::ExpectExpr;
+                        } else {
+                            self.state = FsmState::AfterExpr;
+                        }
+                    },
+                    '}' if self.state_stack.last().map(|x| x.nesting) == Some(Nesting::Brace(BraceKind::TemplateSubstitution)) => {
+                        token = self.consume_template();
+                        if token == RawKind::TemplateMiddle {
+                            self.ternary_depth = 0;
+                            self.state = FsmState::ExpectExpr;
+                        } else {
+                            self.pop_state();
+                        }
                     },
                     '}' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        let (_, st1, sc) = self.state_stack.pop().unwrap_or(('{', FsmState::Initial, false));
-                        self.state = st1;
-                        self.in_for = sc;
+                        token = RawKind::Punctuation;
+                        self.pop_state();
                     },
                     '[' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        self.state_stack.push((']', FsmState::AfterExpr, self.in_for));
-                        self.in_for = false;
+                        token = RawKind::Punctuation;
+                        self.push_state(Nesting::Bracket, FsmState::AfterExpr);
                         self.state = FsmState::ExpectExpr;
                     },
                     ']' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        let (_, st1, sc) = self.state_stack.pop().unwrap_or(('[', FsmState::Initial, false));
-                        self.state = st1;
-                        self.in_for = sc;
+                        token = RawKind::Punctuation;
+                        self.pop_state();
                     },
                     
                     '!' => {
                         match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
                             (Some('='), Some('=')) => {
                                 self.char_iter.skip(3);
-                                token = JsToken::Punctuation("!==".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (Some('='), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("!=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("!".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -502,50 +1203,70 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
                             (Some('='), Some('=')) => {
                                 self.char_iter.skip(3);
-                                token = JsToken::Punctuation("===".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (Some('='), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("==".to_owned());
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('>'), _) => {
+                                self.char_iter.skip(2);
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
                     },
                     '&' => {
-                        match self.char_iter.lookahead(1) {
-                            Some('&') => {
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
+                            (Some('&'), Some('=')) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('&'), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("&&".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            Some('=') => {
+                            (Some('='), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("&=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            _ => {
+                            (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("&".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
                     },
                     '*' => {
-                        match self.char_iter.lookahead(1) {
-                            Some('=') => {
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
+                            (Some('*'), Some('=')) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('*'), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("*=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            _ => {
+                            (Some('='), _) => {
+                                self.char_iter.skip(2);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("*".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -554,12 +1275,12 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match self.char_iter.lookahead(1) {
                             Some('=') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("+=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             Some('+') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("++".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = match (self.state, self.lexer_vars.is_new_line) {
                                     (FsmState::AfterExpr, false) => FsmState::AfterExpr, // postfix ++
                                     _ => FsmState::ExpectExpr, // prefix ++
@@ -567,7 +1288,7 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                             },
                             _ => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("+".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -576,12 +1297,12 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match self.char_iter.lookahead(1) {
                             Some('=') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("-=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             Some('-') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("--".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = match (self.state, self.lexer_vars.is_new_line) {
                                     (FsmState::AfterExpr, false) => FsmState::AfterExpr, // postfix --
                                     _ => FsmState::ExpectExpr, // prefix --
@@ -589,7 +1310,7 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                             },
                             _ => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("-".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -598,65 +1319,80 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
                             (Some('<'), Some('=')) => {
                                 self.char_iter.skip(3);
-                                token = JsToken::Punctuation("<<=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (Some('<'), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("<<".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (Some('='), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("<=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("<".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
                     },
                     '>' => {
-                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
-                            (Some('>'), Some('=')) => {
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2), self.char_iter.lookahead(3)) {
+                            (Some('>'), Some('>'), Some('=')) => {
+                                self.char_iter.skip(4);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('>'), Some('>'), _) => {
                                 self.char_iter.skip(3);
-                                token = JsToken::Punctuation(">>=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            (Some('>'), _) => {
+                            (Some('>'), Some('='), _) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('>'), _, _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation(">>".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            (Some('='), _) => {
+                            (Some('='), _, _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation(">=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            (_, _) => {
+                            (_, _, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation(">".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
                     },
                     '|' => {
-                        match self.char_iter.lookahead(1) {
-                            Some('=') => {
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
+                            (Some('='), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("|=".to_owned());
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('|'), Some('=')) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            Some('|') => {
+                            (Some('|'), _) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("||".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
-                            _ => {
+                            (_, _) => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("|".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -665,12 +1401,12 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match self.char_iter.lookahead(1) {
                             Some('=') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("%=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             _ => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("%".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
@@ -679,50 +1415,108 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                         match self.char_iter.lookahead(1) {
                             Some('=') => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("^=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             _ => {
                                 self.char_iter.skip(1);
-                                token = JsToken::Punctuation("^".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             }
                         }
                     },
-                    ','|':'|'~'|'?' => {
+                    '?' => {
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
+                            (Some('?'), Some('=')) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('?'), _) => {
+                                self.char_iter.skip(2);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('.'), Some('0'..='9')) => {
+                                // `a?.5:b` is a conditional, not optional chaining
+                                self.char_iter.skip(1);
+                                token = RawKind::Punctuation;
+                                self.ternary_depth += 1;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (Some('.'), _) => {
+                                self.char_iter.skip(2);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::Initial;
+                            },
+                            (_, _) => {
+                                self.char_iter.skip(1);
+                                token = RawKind::Punctuation;
+                                self.ternary_depth += 1;
+                                self.state = FsmState::ExpectExpr;
+                            }
+                        }
+                    },
+                    ':' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
+                        token = RawKind::Punctuation;
+                        if self.ternary_depth > 0 {
+                            self.ternary_depth -= 1;
+                            self.state = FsmState::ExpectExpr;
+                        } else if self.is_statement_colon() {
+                            // label or `case ...:`, a statement follows
+                            self.state = FsmState::Initial;
+                        } else {
+                            self.state = FsmState::ExpectExpr;
+                        }
+                    },
+                    ','|'~' => {
+                        self.char_iter.read();
+                        token = RawKind::Punctuation;
                         self.state = FsmState::ExpectExpr;
                     },
                     '/' => {
                         match (self.char_iter.lookahead(1), self.state) {
                             (Some('/'), _) => token = self.consume_line_comment(),
                             (Some('*'), _) => token = self.consume_multiline_comment(),
-                            (Some('='), _) => {
+                            (Some('='), FsmState::AfterExpr) => {
                                 self.char_iter.skip(2);
-                                token = JsToken::Punctuation("/=".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (_, FsmState::AfterExpr) => {
                                 self.char_iter.read();
-                                token = JsToken::Punctuation("/".to_owned());
+                                token = RawKind::Punctuation;
                                 self.state = FsmState::ExpectExpr;
                             },
                             (_, _) => {
                                 self.char_iter.read();
-                                token = self.consume_regexp();
                                 self.state = FsmState::AfterExpr;
+                                token = self.consume_regexp();
                             }
                         }
                     },
                     '.' => {
-                        self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
-                        self.state = FsmState::Initial;
+                        match (self.char_iter.lookahead(1), self.char_iter.lookahead(2)) {
+                            (Some('0'..='9'), _) => {
+                                token = self.consume_number();
+                                self.state = FsmState::AfterExpr;
+                            },
+                            (Some('.'), Some('.')) => {
+                                self.char_iter.skip(3);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::ExpectExpr;
+                            },
+                            (_, _) => {
+                                self.char_iter.skip(1);
+                                token = RawKind::Punctuation;
+                                self.state = FsmState::Initial;
+                            }
+                        }
                     },
                     ';' => {
                         self.char_iter.read();
-                        token = JsToken::Punctuation(format!("{}", c));
+                        token = RawKind::Punctuation;
                         if self.in_for {
                             self.state = FsmState::ExpectExpr;
                         } else {
@@ -731,22 +1525,38 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                     },
                     _ => {
                         self.char_iter.read();
-                        token = JsToken::Unknown(format!("{}", c));
-                        self.state = FsmState::Initial;
+                        token = RawKind::Unknown;
+                        let start = self.token_start;
+                        self.report(DiagnosticCode::UnexpectedCharacter, start).message = format!("unexpected character {:?}", c);
+                        self.resync();
                     },
                 }
                 
                 //println!(" -> {:?}", token);
                 
+                self.at_start = false;
+                
+                let kind = token;
+                let token = JsTokenRef::from_raw(kind, self.char_iter.token_text());
+                // only a few punctuators matter here, so they are not looked up in the whole table
+                let punctuator = match token {
+                    JsTokenRef::Punctuation("=>") => Some(Punctuator::Arrow),
+                    JsTokenRef::Punctuation(")") => Some(Punctuator::RParen),
+                    JsTokenRef::Punctuation(".") => Some(Punctuator::Dot),
+                    JsTokenRef::Punctuation("?.") => Some(Punctuator::QuestionDot),
+                    _ => None,
+                };
+                
                 match token {
-                    JsToken::LineTerminator(_) => {
+                    JsTokenRef::LineTerminator(_) => {
                         self.lexer_vars.is_new_line = true;
                     },
-                    JsToken::MultilineComment(ref x) if x.contains("\n") => {
+                    JsTokenRef::MultilineComment(x) if x.contains(is_line_terminator) => {
                         self.lexer_vars.is_new_line = true;
                     },
-                    JsToken::Whitespace(_) |
-                    JsToken::LineComment(_) => {
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) => {
                         // nothing
                     },
                     _ => {
@@ -755,22 +1565,23 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                 }
                 
                 match token {
-                    JsToken::LineTerminator(_) => {
+                    JsTokenRef::LineTerminator(_) => {
                         if self.lexer_vars.last_token_disallows_newline {
                             self.state = FsmState::Initial;
                         }
                     },
-                    JsToken::MultilineComment(ref x) if x.contains("\n") => {
+                    JsTokenRef::MultilineComment(x) if x.contains(is_line_terminator) => {
                         if self.lexer_vars.last_token_disallows_newline {
                             self.state = FsmState::Initial;
                         }
                     },
-                    JsToken::Word(ref x) if x == "return" || x == "continue" || x == "break" || x == "throw" || x == "yield" => {
+                    _ if matches!(keyword, Some(Keyword::Return) | Some(Keyword::Continue) | Some(Keyword::Break) | Some(Keyword::Throw) | Some(Keyword::Yield)) => {
                         self.lexer_vars.last_token_disallows_newline = true;
                         self.state = FsmState::ExpectExpr;
                     },
-                    JsToken::Whitespace(_) |
-                    JsToken::LineComment(_) => {
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) => {
                         // nothing
                     },
                     _ => {
@@ -779,13 +1590,14 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                 }
                 
                 match token {
-                    JsToken::Word(ref x) if x == "if" || x == "for" || x == "while" => {
+                    _ if matches!(keyword, Some(Keyword::If) | Some(Keyword::For) | Some(Keyword::While) | Some(Keyword::With)) => {
                         self.lexer_vars.last_token_nonexpr_paren = true;
                     },
-                    JsToken::Whitespace(_) |
-                    JsToken::LineComment(_) |
-                    JsToken::LineTerminator(_) |
-                    JsToken::MultilineComment(_) => {
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
                         // nothing
                     },
                     _ => {
@@ -794,21 +1606,118 @@ impl<TIterator: Iterator<Item=char>> Iterator for JsTokenIterator<TIterator> {
                 }
                 
                 match token {
-                    JsToken::Word(ref x) if x == "for" => {
+                    _ if keyword == Some(Keyword::For) => {
                         self.lexer_vars.last_token_for = true;
                     },
-                    JsToken::Whitespace(_) |
-                    JsToken::LineComment(_) |
-                    JsToken::LineTerminator(_) |
-                    JsToken::MultilineComment(_) => {
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
                         // nothing
                     },
                     _ => {
                         self.lexer_vars.last_token_for = false;
                     }
                 }
+                
+                match token {
+                    _ if punctuator == Some(Punctuator::Arrow) => {
+                        self.lexer_vars.last_token_arrow = true;
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_arrow = false;
+                    }
+                }
+                
+                match token {
+                    _ if punctuator == Some(Punctuator::RParen) => {
+                        self.lexer_vars.last_token_close_paren = last_closed_paren;
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_close_paren = None;
+                    }
+                }
+                
+                match token {
+                    _ if contextual == Some(ContextualKeyword::Async) => {
+                        self.lexer_vars.last_token_async = Some(if self.lexer_vars.last_token_export_default { FsmState::Initial } else { prev_state });
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_async = None;
+                    }
+                }
+                
+                match token {
+                    _ if keyword == Some(Keyword::Default) && self.lexer_vars.last_token_export => {
+                        self.lexer_vars.last_token_export_default = true;
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_export_default = false;
+                    }
+                }
+                
+                match token {
+                    _ if keyword == Some(Keyword::Export) => {
+                        self.lexer_vars.last_token_export = true;
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_export = false;
+                    }
+                }
+                
+                match token {
+                    _ if punctuator == Some(Punctuator::Dot) || punctuator == Some(Punctuator::QuestionDot) => {
+                        self.lexer_vars.last_token_dot = true;
+                    },
+                    JsTokenRef::Whitespace(_) |
+                    JsTokenRef::LineComment(_) |
+                    JsTokenRef::Hashbang(_) |
+                    JsTokenRef::LineTerminator(_) |
+                    JsTokenRef::MultilineComment(_) => {
+                        // nothing
+                    },
+                    _ => {
+                        self.lexer_vars.last_token_dot = false;
+                    }
+                }
                     
-                Some(token)
+                Some(kind)
             }
         }
     }
@@ -0,0 +1,319 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use is_line_terminator;
+use {JsTokenIterator, SpannedTokenIterator, Token};
+
+/// A line/column location. Lines and all columns are 1-based.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Byte offset in the source.
+    pub offset: usize,
+    pub line: usize,
+    /// Column in Unicode scalar values.
+    pub column: usize,
+    /// Column in UTF-16 code units, as used by LSP and source maps.
+    pub column_utf16: usize,
+    /// Column on screen, with tabs expanded to the next tab stop.
+    pub display_column: usize,
+}
+
+/// Maps byte offsets in a source text to line/column positions and back.
+///
+/// `\r\n`, `\r`, `\n`, U+2028 and U+2029 end a line, same as the LineTerminator tokens.
+pub struct LineIndex<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+    tab_width: usize,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        LineIndex::with_tab_width(src, 4)
+    }
+
+    pub fn with_tab_width(src: &'a str, tab_width: usize) -> Self {
+        let mut line_starts = vec![0];
+        let mut chars = src.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '\r' && chars.peek().map(|x| x.1) == Some('\n') {
+                continue;
+            }
+            if is_line_terminator(c) {
+                line_starts.push(i + c.len_utf8());
+            }
+        }
+        LineIndex {
+            src,
+            line_starts,
+            tab_width: tab_width.max(1),
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Text of a 1-based line, without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_content_end(line - 1);
+        Some(&self.src[start..end])
+    }
+
+    /// Position of a byte offset. Offsets past the end are clamped to the end of the source,
+    /// offsets inside a character are moved to its start.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.src.len());
+        while !self.src.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let mut position = Position {
+            offset: self.line_starts[line],
+            line: line + 1,
+            column: 1,
+            column_utf16: 1,
+            display_column: 1,
+        };
+        for c in self.src[self.line_starts[line]..offset].chars() {
+            self.advance(&mut position, c);
+        }
+        position
+    }
+
+    /// Same as `position(offset)`, but walks on from `from` when `offset` is after it on the
+    /// same line, so that nearby offsets on a long line do not walk it from its start.
+    pub fn position_from(&self, from: Position, offset: usize) -> Position {
+        let line_end = self.line_starts.get(from.line).cloned().unwrap_or(usize::MAX);
+        if offset < from.offset || offset >= line_end || offset > self.src.len() || !self.src.is_char_boundary(offset) {
+            return self.position(offset);
+        }
+        let mut position = from;
+        for c in self.src[from.offset..offset].chars() {
+            self.advance(&mut position, c);
+        }
+        position
+    }
+
+    /// Byte offset of a 1-based line and Unicode scalar column.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        self.find_offset(line, column, |position| position.column)
+    }
+
+    /// Byte offset of a 1-based line and UTF-16 column.
+    pub fn offset_utf16(&self, line: usize, column_utf16: usize) -> Option<usize> {
+        self.find_offset(line, column_utf16, |position| position.column_utf16)
+    }
+
+    fn find_offset<F: Fn(&Position) -> usize>(&self, line: usize, column: usize, get_column: F) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_content_end(line - 1);
+        let mut position = Position {
+            offset: start,
+            line,
+            column: 1,
+            column_utf16: 1,
+            display_column: 1,
+        };
+        for c in self.src[start..end].chars() {
+            if get_column(&position) >= column {
+                break;
+            }
+            self.advance(&mut position, c);
+        }
+        if get_column(&position) == column {
+            Some(position.offset)
+        } else {
+            None
+        }
+    }
+
+    fn advance(&self, position: &mut Position, c: char) {
+        position.offset += c.len_utf8();
+        position.column += 1;
+        position.column_utf16 += c.len_utf16();
+        if c == '\t' {
+            position.display_column += self.tab_width - (position.display_column - 1) % self.tab_width;
+        } else {
+            position.display_column += 1;
+        }
+    }
+
+    fn line_content_end(&self, line: usize) -> usize {
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) => next,
+            None => return self.src.len(),
+        };
+        let text = &self.src[..end];
+        if text.ends_with("\r\n") {
+            end - 2
+        } else {
+            end - text.chars().next_back().map_or(0, |c| c.len_utf8())
+        }
+    }
+}
+
+/// A line of source text, for when the whole source is not kept; see `LineCollector`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+    /// 1-based line number.
+    pub line: usize,
+    /// Byte offset of the start of the line.
+    pub offset: usize,
+    /// The line without its line terminator.
+    pub text: String,
+}
+
+/// Keeps the lines holding some offsets of a source that is pushed through it a piece at a
+/// time, e.g. for code frames of a source too large to keep. Only the current line and the
+/// kept ones are held in memory. Line breaks are the same as for `LineIndex`.
+pub struct LineCollector {
+    // offsets not reached yet
+    wanted: BinaryHeap<Reverse<usize>>,
+    current: SourceLine,
+    keep_current: bool,
+    // end of the text pushed so far
+    offset: usize,
+    // a `\r` was pushed last, which may be followed by a `\n`
+    after_cr: bool,
+    lines: Vec<SourceLine>,
+}
+
+impl LineCollector {
+    pub fn new() -> Self {
+        LineCollector {
+            wanted: BinaryHeap::new(),
+            current: SourceLine {
+                line: 1,
+                offset: 0,
+                text: String::new(),
+            },
+            keep_current: false,
+            offset: 0,
+            after_cr: false,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Keeps the line holding `offset`. Offsets in lines that were already pushed whole are ignored.
+    pub fn keep_line_at(&mut self, offset: usize) {
+        if offset >= self.offset {
+            self.wanted.push(Reverse(offset));
+        } else if offset >= self.current.offset {
+            self.keep_current = true;
+        }
+    }
+
+    /// Adds the next piece of the source.
+    pub fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.after_cr {
+                self.after_cr = false;
+                if c == '\n' {
+                    self.advance(1);
+                    self.end_line();
+                    continue;
+                }
+                self.end_line();
+            }
+            if c == '\r' {
+                self.after_cr = true;
+                self.advance(1);
+            } else if is_line_terminator(c) {
+                self.advance(c.len_utf8());
+                self.end_line();
+            } else {
+                self.current.text.push(c);
+                self.advance(c.len_utf8());
+            }
+        }
+    }
+
+    /// Ends the source and returns the kept lines, in order.
+    pub fn finish(mut self) -> Vec<SourceLine> {
+        if self.after_cr {
+            self.end_line();
+        }
+        // an offset at the very end is on the last line
+        self.reach(self.offset);
+        if self.keep_current {
+            self.lines.push(self.current);
+        }
+        self.lines
+    }
+
+    /// Moves past a character of `len` bytes in the current line.
+    fn advance(&mut self, len: usize) {
+        let offset = self.offset;
+        self.reach(offset);
+        self.offset += len;
+    }
+
+    fn reach(&mut self, offset: usize) {
+        while self.wanted.peek().is_some_and(|x| x.0 <= offset) {
+            self.wanted.pop();
+            self.keep_current = true;
+        }
+    }
+
+    fn end_line(&mut self) {
+        let next = SourceLine {
+            line: self.current.line + 1,
+            offset: self.offset,
+            text: String::new(),
+        };
+        let line = std::mem::replace(&mut self.current, next);
+        if std::mem::replace(&mut self.keep_current, false) {
+            self.lines.push(line);
+        }
+    }
+}
+
+impl Default for LineCollector {
+    fn default() -> Self {
+        LineCollector::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PositionedToken {
+    pub token: Token,
+    pub start: Position,
+    pub end: Position,
+}
+
+pub struct PositionedTokenIterator<'a, 'b, TIterator: Iterator<Item=char>> {
+    inner: SpannedTokenIterator<TIterator>,
+    index: &'a LineIndex<'b>,
+    // end of the previous token, where the next one starts
+    last: Position,
+}
+
+impl<'a, 'b, TIterator: Iterator<Item=char>> Iterator for PositionedTokenIterator<'a, 'b, TIterator> {
+    type Item = PositionedToken;
+    fn next(&mut self) -> Option<PositionedToken> {
+        let token = self.inner.next()?;
+        let start = self.index.position_from(self.last, token.span.start);
+        let end = self.index.position_from(start, token.span.end);
+        self.last = end;
+        Some(PositionedToken { token, start, end })
+    }
+}
+
+impl<TIterator: Iterator<Item=char>> SpannedTokenIterator<TIterator> {
+    /// Attaches line/column positions from `index`, which must be built from the same source.
+    pub fn with_positions<'a, 'b>(self, index: &'a LineIndex<'b>) -> PositionedTokenIterator<'a, 'b, TIterator> {
+        PositionedTokenIterator {
+            inner: self,
+            index,
+            last: index.position(0),
+        }
+    }
+}
+
+impl<TIterator: Iterator<Item=char>> JsTokenIterator<TIterator> {
+    /// Same as `spanned().with_positions(index)`.
+    pub fn with_positions<'a, 'b>(self, index: &'a LineIndex<'b>) -> PositionedTokenIterator<'a, 'b, TIterator> {
+        self.spanned().with_positions(index)
+    }
+}
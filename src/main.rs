@@ -1,20 +1,68 @@
 extern crate js_lex_rs;
 use js_lex_rs::*;
+use js_lex_rs::line_index::LineCollector;
+use js_lex_rs::reader::InvalidUtf8;
+use js_lex_rs::render::Renderer;
 use std::fs::File;
-use std::io::prelude::*;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::process;
 
 fn main() {
-    let filename = std::env::args().nth(1).expect("File name not passed");
-    
-    let mut f = File::open(&filename).unwrap();
-    let sz = f.metadata().unwrap().len() as usize;
-    let mut s = String::with_capacity(sz);
-    
-    f.read_to_string(&mut s).unwrap();
+    let filename = match std::env::args().nth(1) {
+        Some(filename) => filename,
+        None => {
+            eprintln!("usage: js-lex-rs <file>");
+            process::exit(2);
+        },
+    };
 
-    let tokens = tokenize_str(&s);
-    
-    for token in tokens {
-        println!("{:?}", token);
+    let file = match File::open(&filename) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("{}: cannot read file: {}", filename, err);
+            process::exit(1);
+        },
+    };
+
+    let mut tokens = tokenize_reader(file, InvalidUtf8::Error);
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    // the source is not kept, only the lines needed for the code frames
+    let mut lines = LineCollector::new();
+    let mut diagnostic_count = 0;
+    while let Some(token) = tokens.next() {
+        let token = match token {
+            Ok(token) => token,
+            Err(err) => {
+                let _ = out.flush();
+                eprintln!("{}: {}", filename, err);
+                process::exit(1);
+            },
+        };
+        // diagnostics start inside their token, so their lines are not pushed yet
+        for diagnostic in &tokens.diagnostics()[diagnostic_count..] {
+            lines.keep_line_at(diagnostic.span.start);
+        }
+        diagnostic_count = tokens.diagnostics().len();
+        lines.push_str(&token.source_text());
+        if writeln!(out, "{:?}", token).is_err() {
+            process::exit(1);
+        }
+    }
+    if out.flush().is_err() {
+        process::exit(1);
+    }
+
+    let diagnostics = tokens.take_diagnostics();
+    if diagnostics.is_empty() {
+        return;
+    }
+    let lines = lines.finish();
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let renderer = Renderer::with_lines(&filename, &lines).with_color(color);
+    for diagnostic in &diagnostics {
+        eprint!("{}", renderer.render(diagnostic));
     }
+    process::exit(1);
 }
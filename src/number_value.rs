@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+use {JsToken, JsTokenRef};
+pub use num_bigint::BigUint;
+
+/// The value of a numeric literal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NumberValue {
+    Number(f64),
+    BigInt(BigUint),
+}
+
+/// Evaluates the raw text of a numeric literal, rounding to the nearest `f64` (ties to even)
+/// the same way ECMAScript does. Returns `None` if the text is not a valid literal.
+pub fn number_value(raw: &str) -> Option<NumberValue> {
+    let (text, is_bigint) = match raw.strip_suffix('n') {
+        Some(text) => (text, true),
+        None => (raw, false),
+    };
+
+    // `017` is a legacy octal literal, `089` a decimal one; neither allows `n` or separators
+    let leading_zero = text.len() > 1 && text.starts_with('0') && text.as_bytes()[1].is_ascii_digit();
+    if leading_zero && (is_bigint || text.contains('_')) {
+        return None;
+    }
+
+    let (radix, digits) = match text.get(..2) {
+        Some("0x") | Some("0X") => (16, &text[2..]),
+        Some("0o") | Some("0O") => (8, &text[2..]),
+        Some("0b") | Some("0B") => (2, &text[2..]),
+        _ if leading_zero && text.bytes().all(|b| (b'0'..=b'7').contains(&b)) => (8, &text[1..]),
+        _ => (10, text),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    let digits = remove_separators(digits, radix)?;
+    // `parse_bytes` and `str::parse` are more lenient than the grammar, so check the characters first
+    let is_decimal_number = radix == 10 && !is_bigint;
+    if !has_valid_digits(&digits, radix, is_decimal_number) {
+        return None;
+    }
+
+    if is_bigint {
+        return BigUint::parse_bytes(digits.as_bytes(), radix).map(NumberValue::BigInt);
+    }
+    if radix == 10 {
+        return digits.parse().ok().map(NumberValue::Number);
+    }
+    BigUint::parse_bytes(digits.as_bytes(), radix).map(|value| NumberValue::Number(biguint_to_f64(&value)))
+}
+
+/// Drops `_` separators, checking that each one is between two digits.
+fn remove_separators(digits: &str, radix: u32) -> Option<Cow<'_, str>> {
+    if !digits.contains('_') {
+        return Some(Cow::Borrowed(digits));
+    }
+    if radix == 10 && digits.starts_with("0_") {
+        return None;
+    }
+    let bytes = digits.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b != b'_' {
+            continue;
+        }
+        let is_digit = |b: Option<&u8>| b.is_some_and(|&b| (b as char).is_digit(radix));
+        if i == 0 || !is_digit(bytes.get(i - 1)) || !is_digit(bytes.get(i + 1)) {
+            return None;
+        }
+    }
+    Some(Cow::Owned(digits.replace('_', "")))
+}
+
+/// Checks that `digits` has only digits of `radix`, plus a fraction and an exponent if `allow_fraction`.
+/// Rejects the signs, `inf` and `NaN` that the parsers would accept.
+fn has_valid_digits(digits: &str, radix: u32, allow_fraction: bool) -> bool {
+    let bytes = digits.as_bytes();
+    bytes.iter().enumerate().all(|(i, &b)| match b {
+        b'+' | b'-' => allow_fraction && i > 0 && (bytes[i - 1] == b'e' || bytes[i - 1] == b'E'),
+        b'.' | b'e' | b'E' if allow_fraction => true,
+        _ => (b as char).is_digit(radix),
+    })
+}
+
+/// Converts to the nearest `f64`, ties to even.
+fn biguint_to_f64(value: &BigUint) -> f64 {
+    let bits = value.bits();
+    if bits <= 64 {
+        return value.to_u64_digits().first().cloned().unwrap_or(0) as f64;
+    }
+    if bits > 1024 {
+        return f64::INFINITY;
+    }
+    // keep the top 64 bits, plus a sticky bit for anything below them, so that
+    // the `u64` to `f64` conversion rounds the same as the exact value would
+    let shift = bits - 64;
+    let mut top = (value >> shift).to_u64_digits()[0];
+    if value.trailing_zeros().unwrap_or(0) < shift {
+        top |= 1;
+    }
+    top as f64 * 2f64.powi(shift as i32)
+}
+
+impl JsToken {
+    /// For a `NumberLiteral`, its value; see `number_value`.
+    pub fn number_value(&self) -> Option<NumberValue> {
+        self.as_token_ref().number_value()
+    }
+}
+
+impl<'a> JsTokenRef<'a> {
+    /// For a `NumberLiteral`, its value; see `number_value`.
+    pub fn number_value(&self) -> Option<NumberValue> {
+        match *self {
+            JsTokenRef::NumberLiteral(raw) => number_value(raw),
+            _ => None,
+        }
+    }
+}
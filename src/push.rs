@@ -0,0 +1,104 @@
+use byte_stream::ByteStream;
+use diagnostic::Diagnostic;
+use lex_stream::CharSource;
+use {JsTokenRef, Lexer, Span, Token};
+
+/// Lexes input that arrives in chunks, e.g. from a socket or a pipe.
+///
+/// `feed` returns the tokens that no further input can change and keeps the rest; `finish`
+/// lexes what is left at the end of the input. The tokens, spans and diagnostics are the same
+/// as for the whole input at once, wherever the chunks are split.
+///
+/// An incomplete token is lexed again from its start on each `feed`, so a very long token
+/// fed in tiny chunks takes quadratic time.
+pub struct PushLexer {
+    // the state after the last complete token; its source is an empty placeholder between calls
+    lexer: Lexer<ByteStream<'static>>,
+    // input after the last complete token
+    pending: String,
+    // byte and UTF-16 offsets of `pending` in the whole input
+    pending_start: (usize, usize),
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PushLexer {
+    pub fn new() -> Self {
+        PushLexer {
+            lexer: Lexer::new(ByteStream::new("")),
+            pending: String::new(),
+            pending_start: (0, 0),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Adds the next chunk of input and returns the tokens completed by it.
+    pub fn feed(&mut self, chunk: &str) -> Vec<Token> {
+        self.pending.push_str(chunk);
+        self.lex(false)
+    }
+
+    /// Ends the input and returns the remaining tokens.
+    pub fn finish(&mut self) -> Vec<Token> {
+        self.lex(true)
+    }
+
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn lex(&mut self, at_end: bool) -> Vec<Token> {
+        let lexer = std::mem::replace(&mut self.lexer, Lexer::new(ByteStream::new("")));
+        let mut lexer = lexer.with_source(ByteStream::new(&self.pending));
+        let mut tokens = Vec::new();
+        loop {
+            let checkpoint = lexer.checkpoint();
+            let start = (lexer.char_iter.offset(), lexer.char_iter.offset_utf16());
+            let kind = lexer.next_raw();
+            // a token that ran into the end of the chunk may go on in the next one
+            if lexer.char_iter.take_reached_end() && !at_end {
+                lexer.rewind(checkpoint);
+                break;
+            }
+            let kind = match kind {
+                Some(kind) => kind,
+                None => break,
+            };
+            let span = self.absolute_span(start, (lexer.char_iter.offset(), lexer.char_iter.offset_utf16()));
+            tokens.push(Token {
+                kind: JsTokenRef::from_raw(kind, lexer.char_iter.token_slice()).into(),
+                span,
+            });
+            for mut diagnostic in lexer.diagnostics.drain(..) {
+                let span = diagnostic.span;
+                diagnostic.span = self.absolute_span((span.start, span.start_utf16), (span.end, span.end_utf16));
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        let consumed = (lexer.char_iter.offset(), lexer.char_iter.offset_utf16());
+        self.lexer = lexer.with_source(ByteStream::new(""));
+        self.pending.drain(..consumed.0);
+        self.pending_start = (self.pending_start.0 + consumed.0, self.pending_start.1 + consumed.1);
+        tokens
+    }
+
+    fn absolute_span(&self, start: (usize, usize), end: (usize, usize)) -> Span {
+        Span {
+            start: self.pending_start.0 + start.0,
+            end: self.pending_start.0 + end.0,
+            start_utf16: self.pending_start.1 + start.1,
+            end_utf16: self.pending_start.1 + end.1,
+        }
+    }
+}
+
+impl Default for PushLexer {
+    fn default() -> Self {
+        PushLexer::new()
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::{self, Read};
+use diagnostic::Diagnostic;
+use {JsToken, JsTokenIterator};
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// What to do with bytes that are not valid UTF-8.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Replace each maximal invalid subsequence with U+FFFD, the same as `String::from_utf8_lossy`.
+    Replace,
+    /// Stop with an `InvalidData` error.
+    Error,
+}
+
+/// Decodes UTF-8 from a reader, a buffer at a time.
+///
+/// Iteration ends at the end of the input or at the first error; see `take_error`.
+pub struct Utf8Chars<R: Read> {
+    reader: R,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    eof: bool,
+    // bytes decoded before `buf[start]`, for error messages
+    offset: usize,
+    on_invalid: InvalidUtf8,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Utf8Chars<R> {
+    pub fn new(reader: R, on_invalid: InvalidUtf8) -> Self {
+        Utf8Chars {
+            reader,
+            buf: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            eof: false,
+            offset: 0,
+            on_invalid,
+            error: None,
+        }
+    }
+
+    /// The error that ended the iteration, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Makes at least `count` bytes available unless the input ends first.
+    fn fill(&mut self, count: usize) -> io::Result<()> {
+        while self.end - self.start < count && !self.eof {
+            if self.start > 0 {
+                self.buf.copy_within(self.start..self.end, 0);
+                self.end -= self.start;
+                self.start = 0;
+            }
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.end += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `error` and ends the iteration.
+    fn fail(&mut self, error: io::Error) {
+        self.error = Some(error);
+        self.eof = true;
+        self.start = self.end;
+    }
+
+    fn consume(&mut self, count: usize) {
+        self.start += count;
+        self.offset += count;
+    }
+
+    /// Decodes the character at `start`, or returns the length of the invalid sequence there.
+    fn decode(&self) -> Result<(char, usize), usize> {
+        let bytes = &self.buf[self.start..self.end];
+        let first = bytes[0];
+        let (len, second_range) = match first {
+            0x00..=0x7f => return Ok((first as char, 1)),
+            0xc2..=0xdf => (2, 0x80..=0xbf),
+            0xe0 => (3, 0xa0..=0xbf),
+            0xed => (3, 0x80..=0x9f),
+            0xe1..=0xef => (3, 0x80..=0xbf),
+            0xf0 => (4, 0x90..=0xbf),
+            0xf1..=0xf3 => (4, 0x80..=0xbf),
+            0xf4 => (4, 0x80..=0x8f),
+            _ => return Err(1),
+        };
+        let mut value = first as u32 & (0x7f >> len);
+        for i in 1..len {
+            let valid = match bytes.get(i) {
+                Some(&b) if i == 1 => second_range.contains(&b),
+                Some(&b) => (0x80..=0xbf).contains(&b),
+                None => false,
+            };
+            if !valid {
+                return Err(i);
+            }
+            value = (value << 6) | (bytes[i] as u32 & 0x3f);
+        }
+        // the ranges above leave out overlong forms, surrogates and values past U+10FFFF
+        Ok((std::char::from_u32(value).unwrap(), len))
+    }
+}
+
+impl<R: Read> Iterator for Utf8Chars<R> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        if let Err(e) = self.fill(4) {
+            self.fail(e);
+            return None;
+        }
+        if self.start == self.end {
+            return None;
+        }
+        match self.decode() {
+            Ok((c, len)) => {
+                self.consume(len);
+                Some(c)
+            },
+            Err(len) => match self.on_invalid {
+                InvalidUtf8::Replace => {
+                    self.consume(len);
+                    Some('\u{fffd}')
+                },
+                InvalidUtf8::Error => {
+                    let message = format!("stream did not contain valid UTF-8 at byte {}", self.offset);
+                    self.fail(io::Error::new(io::ErrorKind::InvalidData, message));
+                    None
+                },
+            },
+        }
+    }
+}
+
+/// Tokens lexed from a reader; see `tokenize_reader`.
+pub struct ReaderTokenIterator<R: Read> {
+    inner: JsTokenIterator<Utf8Chars<R>>,
+    done: bool,
+}
+
+impl<R: Read> ReaderTokenIterator<R> {
+    pub(crate) fn new(inner: JsTokenIterator<Utf8Chars<R>>) -> Self {
+        ReaderTokenIterator { inner, done: false }
+    }
+
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.inner.diagnostics()
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.inner.take_diagnostics()
+    }
+}
+
+impl<R: Read> Iterator for ReaderTokenIterator<R> {
+    type Item = io::Result<JsToken>;
+    fn next(&mut self) -> Option<io::Result<JsToken>> {
+        if self.done {
+            return None;
+        }
+        let token = self.inner.next();
+        // the error may have cut the token short, so it is returned instead of the token
+        if let Some(e) = self.inner.char_iter.get_mut().take_error() {
+            self.done = true;
+            return Some(Err(e));
+        }
+        token.map(Ok)
+    }
+}
@@ -0,0 +1,1075 @@
+use std::fmt;
+use identifier::{is_identifier_part, is_identifier_start};
+use {is_valid_regexp_flags, JsToken, JsTokenRef};
+
+/// A parsed regular expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// Always a `NodeKind::Disjunction`.
+    pub body: Node,
+    pub flags: Flags,
+    /// Number of capturing groups, named ones included.
+    pub group_count: u32,
+    /// Names of the named groups, in source order.
+    pub group_names: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Flags {
+    pub has_indices: bool,
+    pub global: bool,
+    pub ignore_case: bool,
+    pub multiline: bool,
+    pub dot_all: bool,
+    pub unicode: bool,
+    pub unicode_sets: bool,
+    pub sticky: bool,
+}
+
+impl Flags {
+    /// Parses the flags after the closing `/`, see `is_valid_regexp_flags`.
+    pub fn parse(flags: &str) -> Option<Flags> {
+        if !is_valid_regexp_flags(flags) {
+            return None;
+        }
+        Some(Flags {
+            has_indices: flags.contains('d'),
+            global: flags.contains('g'),
+            ignore_case: flags.contains('i'),
+            multiline: flags.contains('m'),
+            dot_all: flags.contains('s'),
+            unicode: flags.contains('u'),
+            unicode_sets: flags.contains('v'),
+            sticky: flags.contains('y'),
+        })
+    }
+
+    /// `u` or `v`: the pattern is matched by code points and the stricter grammar applies.
+    pub fn is_unicode_mode(&self) -> bool {
+        self.unicode || self.unicode_sets
+    }
+}
+
+/// A node of the pattern, with its byte range in the pattern text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// `a|b`, a list of `Alternative`s.
+    Disjunction(Vec<Node>),
+    /// A sequence of terms.
+    Alternative(Vec<Node>),
+    Assertion(Assertion),
+    /// `(?=...)`, `(?!...)`, `(?<=...)`, `(?<!...)`
+    Lookaround {
+        behind: bool,
+        negated: bool,
+        body: Box<Node>,
+    },
+    /// `(...)` or `(?<name>...)`; `index` counts from 1.
+    Group {
+        index: u32,
+        name: Option<String>,
+        body: Box<Node>,
+    },
+    /// `(?:...)`
+    NonCapturingGroup(Box<Node>),
+    Quantifier {
+        min: u32,
+        /// `None` for no upper bound.
+        max: Option<u32>,
+        greedy: bool,
+        body: Box<Node>,
+    },
+    /// A code point in `u`/`v` mode, a UTF-16 code unit otherwise. Lone surrogates are possible.
+    Char(u32),
+    /// `.`
+    Dot,
+    ClassEscape(ClassEscape),
+    Class(CharacterClass),
+    Backreference(Backreference),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Assertion {
+    /// `^`
+    Start,
+    /// `$`
+    End,
+    /// `\b`
+    WordBoundary,
+    /// `\B`
+    NotWordBoundary,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ClassEscape {
+    Digit,
+    NotDigit,
+    Space,
+    NotSpace,
+    Word,
+    NotWord,
+    /// `\p{name}`, `\p{name=value}`, or `\P{...}` when negated.
+    Property {
+        name: String,
+        value: Option<String>,
+        negated: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backreference {
+    Index(u32),
+    Named(String),
+}
+
+/// `[...]`. Only `v` mode has intersections, subtractions and nested classes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharacterClass {
+    pub negated: bool,
+    pub op: ClassSetOp,
+    pub items: Vec<ClassItem>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ClassSetOp {
+    Union,
+    /// `[a&&b]`
+    Intersection,
+    /// `[a--b]`
+    Subtraction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClassItem {
+    Char(u32),
+    Range(u32, u32),
+    Escape(ClassEscape),
+    Class(CharacterClass),
+    /// `\q{abc|d}`, each string as code points.
+    Strings(Vec<Vec<u32>>),
+}
+
+impl CharacterClass {
+    /// Whether the class can match a string other than a single code point (`v` mode only).
+    pub fn may_contain_strings(&self) -> bool {
+        let item_may_contain_strings = |item: &ClassItem| match *item {
+            ClassItem::Strings(ref strings) => strings.iter().any(|s| s.len() != 1),
+            ClassItem::Class(ref class) => class.may_contain_strings(),
+            ClassItem::Escape(ClassEscape::Property { ref name, ref value, .. }) => {
+                value.is_none() && PROPERTIES_OF_STRINGS.contains(&&name[..])
+            },
+            _ => false,
+        };
+        match self.op {
+            ClassSetOp::Union => self.items.iter().any(item_may_contain_strings),
+            ClassSetOp::Intersection => self.items.iter().all(item_may_contain_strings),
+            ClassSetOp::Subtraction => self.items.first().is_some_and(item_may_contain_strings),
+        }
+    }
+}
+
+/// An early error in a pattern. `offset` is a byte offset in the pattern text;
+/// errors in the flags are reported at the end of the pattern.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RegexpError {
+    pub message: &'static str,
+    pub offset: usize,
+}
+
+impl fmt::Display for RegexpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+/// Parses a pattern (the text between the slashes) with the given flags.
+///
+/// Without `u` or `v` the Annex B grammar is used, so `/]/` and `/\c/` are valid there.
+/// Script names in `\p{Script=...}` are only checked for syntax.
+/// Duplicate group names are an error even in different alternatives.
+pub fn parse_regexp(pattern: &str, flags: &str) -> Result<Pattern, RegexpError> {
+    let flags = match Flags::parse(flags) {
+        Some(flags) => flags,
+        None => return Err(RegexpError { message: "invalid regular expression flags", offset: pattern.len() }),
+    };
+    let (group_count, has_named_groups) = count_groups(pattern, flags.unicode_sets);
+    let mut parser = Parser {
+        src: pattern,
+        pos: 0,
+        unicode: flags.is_unicode_mode(),
+        unicode_sets: flags.unicode_sets,
+        named_groups: flags.is_unicode_mode() || has_named_groups,
+        group_count,
+        next_group: 1,
+        group_names: Vec::new(),
+        named_references: Vec::new(),
+    };
+
+    let body = parser.parse_disjunction()?;
+    if parser.pos < pattern.len() {
+        return parser.error("unmatched ')'");
+    }
+    for &(ref name, offset) in &parser.named_references {
+        if !parser.group_names.contains(name) {
+            return Err(RegexpError { message: "reference to an undefined group name", offset });
+        }
+    }
+
+    Ok(Pattern {
+        body,
+        flags,
+        group_count,
+        group_names: parser.group_names,
+    })
+}
+
+/// Counts capturing groups ahead of parsing, as `\2` may come before the second group.
+fn count_groups(pattern: &str, unicode_sets: bool) -> (u32, bool) {
+    let mut count = 0;
+    let mut has_named_groups = false;
+    let mut class_depth = 0;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            },
+            '[' if class_depth == 0 || unicode_sets => class_depth += 1,
+            ']' if class_depth > 0 => class_depth -= 1,
+            '(' if class_depth == 0 => {
+                if chars.peek() != Some(&'?') {
+                    count += 1;
+                    continue;
+                }
+                chars.next();
+                if chars.peek() == Some(&'<') {
+                    chars.next();
+                    if chars.peek() != Some(&'=') && chars.peek() != Some(&'!') {
+                        count += 1;
+                        has_named_groups = true;
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    (count, has_named_groups)
+}
+
+const SYNTAX_CHARACTERS: &str = "^$\\.*+?()[]{}|";
+const CLASS_SET_SYNTAX_CHARACTERS: &str = "()[]{}/-\\|";
+const CLASS_SET_RESERVED_PUNCTUATORS: &str = "&-!#%,:;<=>@`~";
+const CLASS_SET_RESERVED_DOUBLE_PUNCTUATORS: &str = "&!#$%*+,.:;<=>?@^`~";
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    unicode: bool,
+    unicode_sets: bool,
+    // `\k<name>` is a reference rather than an identity escape
+    named_groups: bool,
+    group_count: u32,
+    next_group: u32,
+    group_names: Vec<String>,
+    named_references: Vec<(String, usize)>,
+}
+
+enum ClassAtom {
+    Char(u32),
+    Escape(ClassEscape),
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &'static str) -> Result<T, RegexpError> {
+        Err(RegexpError { message, offset: self.pos })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn looking_at(&self, s: &str) -> bool {
+        self.src[self.pos..].starts_with(s)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.looking_at(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn node(&self, kind: NodeKind, start: usize) -> Node {
+        Node { kind, start, end: self.pos }
+    }
+
+    /// Pushes a source character, split into surrogates outside of unicode mode.
+    /// Returns the code point or the low surrogate, which is what a quantifier applies to.
+    fn char_units(&self, c: char, mut push: impl FnMut(u32)) -> u32 {
+        let mut buf = [0; 2];
+        if self.unicode || c.len_utf16() == 1 {
+            c as u32
+        } else {
+            let units = c.encode_utf16(&mut buf);
+            push(units[0] as u32);
+            units[1] as u32
+        }
+    }
+
+    fn parse_disjunction(&mut self) -> Result<Node, RegexpError> {
+        let start = self.pos;
+        let mut alternatives = vec![self.parse_alternative()?];
+        while self.eat("|") {
+            alternatives.push(self.parse_alternative()?);
+        }
+        Ok(self.node(NodeKind::Disjunction(alternatives), start))
+    }
+
+    fn parse_alternative(&mut self) -> Result<Node, RegexpError> {
+        let start = self.pos;
+        let mut terms = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            self.parse_term(&mut terms)?;
+        }
+        Ok(self.node(NodeKind::Alternative(terms), start))
+    }
+
+    fn parse_term(&mut self, terms: &mut Vec<Node>) -> Result<(), RegexpError> {
+        let start = self.pos;
+        let c = self.peek().unwrap();
+        let (atom, quantifiable) = match c {
+            '^' | '$' => {
+                self.next();
+                let assertion = if c == '^' { Assertion::Start } else { Assertion::End };
+                (NodeKind::Assertion(assertion), false)
+            },
+            '\\' if self.peek_at(1) == Some('b') || self.peek_at(1) == Some('B') => {
+                self.pos += 2;
+                let assertion = if self.src[..self.pos].ends_with('b') { Assertion::WordBoundary } else { Assertion::NotWordBoundary };
+                (NodeKind::Assertion(assertion), false)
+            },
+            '(' => self.parse_group()?,
+            '*' | '+' | '?' => return self.error("nothing to repeat"),
+            '{' if self.unicode => return self.error("lone quantifier brackets"),
+            '{' if self.parse_braced_quantifier()?.is_some() => {
+                self.pos = start;
+                return self.error("nothing to repeat");
+            },
+            '}' | ']' if self.unicode => return self.error("lone quantifier brackets"),
+            '[' => (NodeKind::Class(self.parse_class()?), true),
+            '.' => {
+                self.next();
+                (NodeKind::Dot, true)
+            },
+            '\\' => (self.parse_atom_escape(terms, start)?, true),
+            _ => {
+                self.next();
+                let unit = self.char_units(c, |high| terms.push(Node { kind: NodeKind::Char(high), start, end: start + c.len_utf8() }));
+                (NodeKind::Char(unit), true)
+            },
+        };
+        let atom = self.node(atom, start);
+
+        let quantifier_start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_braced_quantifier()? {
+                Some((min, max)) => {
+                    self.pos = quantifier_start;
+                    (min, max)
+                },
+                None => {
+                    terms.push(atom);
+                    return Ok(());
+                },
+            },
+            _ => {
+                terms.push(atom);
+                return Ok(());
+            },
+        };
+        if !quantifiable {
+            return self.error("nothing to repeat");
+        }
+        if let NodeKind::Lookaround { behind, .. } = atom.kind {
+            if behind || self.unicode {
+                return self.error("invalid quantifier on a lookaround");
+            }
+        }
+        if max.is_some_and(|max| max < min) {
+            return self.error("numbers out of order in {} quantifier");
+        }
+        if self.next() == Some('{') {
+            while self.next() != Some('}') {}
+        }
+        let greedy = !self.eat("?");
+        terms.push(self.node(NodeKind::Quantifier { min, max, greedy, body: Box::new(atom) }, start));
+        Ok(())
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` at the current position, restoring the position.
+    /// `None` if it is not a quantifier; outside of unicode mode that `{` is a literal.
+    fn parse_braced_quantifier(&mut self) -> Result<Option<(u32, Option<u32>)>, RegexpError> {
+        let start = self.pos;
+        self.next();
+        let result = match self.parse_decimal() {
+            None => None,
+            Some(min) => {
+                if self.eat(",") {
+                    match self.parse_decimal() {
+                        Some(max) => Some((min, Some(max))),
+                        None => Some((min, None)),
+                    }
+                } else {
+                    Some((min, Some(min)))
+                }
+            },
+        };
+        let result = if self.eat("}") { result } else { None };
+        self.pos = start;
+        if result.is_none() && self.unicode {
+            return self.error("incomplete quantifier");
+        }
+        Ok(result)
+    }
+
+    fn parse_decimal(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut value = 0u32;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            self.next();
+            value = value.saturating_mul(10).saturating_add(digit);
+        }
+        if self.pos == start {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<(NodeKind, bool), RegexpError> {
+        let start = self.pos;
+        self.next();
+        let (kind, quantifiable);
+        if self.eat("?=") || self.eat("?!") || self.eat("?<=") || self.eat("?<!") {
+            let behind = self.src[..self.pos].ends_with("<=") || self.src[..self.pos].ends_with("<!");
+            let negated = self.src[..self.pos].ends_with('!');
+            let body = self.parse_disjunction()?;
+            kind = NodeKind::Lookaround { behind, negated, body: Box::new(body) };
+            // checked in parse_term: Annex B allows quantified lookaheads outside of unicode mode
+            quantifiable = true;
+        } else if self.eat("?:") {
+            kind = NodeKind::NonCapturingGroup(Box::new(self.parse_disjunction()?));
+            quantifiable = true;
+        } else if self.eat("?<") {
+            let name_start = self.pos;
+            let name = self.parse_group_name()?;
+            if self.group_names.contains(&name) {
+                self.pos = name_start;
+                return self.error("duplicate capture group name");
+            }
+            self.group_names.push(name.clone());
+            let index = self.next_group;
+            self.next_group += 1;
+            let body = self.parse_disjunction()?;
+            kind = NodeKind::Group { index, name: Some(name), body: Box::new(body) };
+            quantifiable = true;
+        } else if self.looking_at("?") {
+            return self.error("invalid group");
+        } else {
+            let index = self.next_group;
+            self.next_group += 1;
+            let body = self.parse_disjunction()?;
+            kind = NodeKind::Group { index, name: None, body: Box::new(body) };
+            quantifiable = true;
+        }
+        if !self.eat(")") {
+            self.pos = start;
+            return self.error("unterminated group");
+        }
+        Ok((kind, quantifiable))
+    }
+
+    /// Parses `name>` after `(?<` or `\k<`.
+    fn parse_group_name(&mut self) -> Result<String, RegexpError> {
+        let start = self.pos;
+        let mut name = String::new();
+        loop {
+            let c = match self.peek() {
+                Some('>') => break,
+                Some('\\') => {
+                    self.next();
+                    if self.next() != Some('u') {
+                        self.pos = start;
+                        return self.error("invalid capture group name");
+                    }
+                    match self.parse_unicode_escape(true).and_then(std::char::from_u32) {
+                        Some(c) => c,
+                        None => {
+                            self.pos = start;
+                            return self.error("invalid capture group name");
+                        },
+                    }
+                },
+                Some(c) => {
+                    self.next();
+                    c
+                },
+                None => {
+                    self.pos = start;
+                    return self.error("invalid capture group name");
+                },
+            };
+            let valid = if name.is_empty() { is_identifier_start(c) } else { is_identifier_part(c) };
+            if !valid {
+                self.pos = start;
+                return self.error("invalid capture group name");
+            }
+            name.push(c);
+        }
+        self.next();
+        if name.is_empty() {
+            self.pos = start;
+            return self.error("invalid capture group name");
+        }
+        Ok(name)
+    }
+
+    /// Parses the part of `\u` escape after the `u`. Surrogate pairs written as two escapes
+    /// are joined, and `\u{...}` is accepted, in unicode mode and in group names.
+    fn parse_unicode_escape(&mut self, code_point_mode: bool) -> Option<u32> {
+        let start = self.pos;
+        if code_point_mode && self.eat("{") {
+            let mut value = 0u32;
+            let digits_start = self.pos;
+            while let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) {
+                self.next();
+                value = value.saturating_mul(16).saturating_add(digit);
+            }
+            if self.pos == digits_start || value > 0x10ffff || !self.eat("}") {
+                self.pos = start;
+                return None;
+            }
+            return Some(value);
+        }
+        let value = self.parse_hex(4)?;
+        if code_point_mode && (0xd800..0xdc00).contains(&value) && self.looking_at("\\u") {
+            let high_end = self.pos;
+            self.pos += 2;
+            match self.parse_hex(4) {
+                Some(low) if (0xdc00..0xe000).contains(&low) => {
+                    return Some(0x10000 + ((value - 0xd800) << 10) + (low - 0xdc00));
+                },
+                _ => self.pos = high_end,
+            }
+        }
+        Some(value)
+    }
+
+    fn parse_hex(&mut self, count: usize) -> Option<u32> {
+        let start = self.pos;
+        let mut value = 0;
+        for _ in 0..count {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(digit) => {
+                    self.next();
+                    value = value * 16 + digit;
+                },
+                None => {
+                    self.pos = start;
+                    return None;
+                },
+            }
+        }
+        Some(value)
+    }
+
+    /// Parses an escape outside of a class; `start` is the position of the backslash.
+    fn parse_atom_escape(&mut self, terms: &mut Vec<Node>, start: usize) -> Result<NodeKind, RegexpError> {
+        self.next();
+        match self.peek() {
+            Some('1'..='9') => {
+                let digits_start = self.pos;
+                let n = self.parse_decimal().unwrap();
+                if n <= self.group_count {
+                    return Ok(NodeKind::Backreference(Backreference::Index(n)));
+                }
+                if self.unicode {
+                    self.pos = start;
+                    return self.error("invalid escape");
+                }
+                // Annex B: a legacy octal escape or an identity escape of `8` or `9`
+                self.pos = digits_start;
+            },
+            Some('k') if self.named_groups => {
+                self.next();
+                if !self.eat("<") {
+                    return self.error("invalid named reference");
+                }
+                let name_start = self.pos;
+                let name = self.parse_group_name()?;
+                self.named_references.push((name.clone(), name_start));
+                return Ok(NodeKind::Backreference(Backreference::Named(name)));
+            },
+            _ => {},
+        }
+        match self.parse_class_escape(false, terms, start)? {
+            ClassAtom::Char(c) => Ok(NodeKind::Char(c)),
+            ClassAtom::Escape(escape) => Ok(NodeKind::ClassEscape(escape)),
+        }
+    }
+
+    /// Parses an escape after the backslash. `\b` is only reached here inside a class.
+    /// `start` is the position of the backslash; outside of unicode mode the high surrogate
+    /// of an astral identity escape is pushed to `terms`.
+    fn parse_class_escape(&mut self, in_class: bool, terms: &mut Vec<Node>, start: usize) -> Result<ClassAtom, RegexpError> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return self.error("\\ at end of pattern"),
+        };
+        let escape = match c {
+            'd' => ClassEscape::Digit,
+            'D' => ClassEscape::NotDigit,
+            's' => ClassEscape::Space,
+            'S' => ClassEscape::NotSpace,
+            'w' => ClassEscape::Word,
+            'W' => ClassEscape::NotWord,
+            'p' | 'P' if self.unicode => return self.parse_property(c == 'P', start).map(ClassAtom::Escape),
+            _ => return self.parse_character_escape(c, in_class, terms, start).map(ClassAtom::Char),
+        };
+        Ok(ClassAtom::Escape(escape))
+    }
+
+    fn parse_character_escape(&mut self, c: char, in_class: bool, terms: &mut Vec<Node>, start: usize) -> Result<u32, RegexpError> {
+        let value = match c {
+            'b' if in_class => 0x08,
+            '-' if in_class && self.unicode => '-' as u32,
+            'f' => 0x0c,
+            'n' => 0x0a,
+            'r' => 0x0d,
+            't' => 0x09,
+            'v' => 0x0b,
+            'c' => match self.peek() {
+                Some(letter) if letter.is_ascii_alphabetic() => {
+                    self.next();
+                    letter as u32 % 32
+                },
+                // Annex B: `\c0` and `\c_` inside a class
+                Some(letter) if in_class && !self.unicode && (letter.is_ascii_digit() || letter == '_') => {
+                    self.next();
+                    letter as u32 % 32
+                },
+                _ if self.unicode => {
+                    self.pos = start;
+                    return self.error("invalid unicode escape");
+                },
+                _ => {
+                    // Annex B: the backslash is a literal and `c` is read again
+                    self.pos = start + 1;
+                    '\\' as u32
+                },
+            },
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => 0,
+            '0'..='7' if !self.unicode => {
+                // Annex B: LegacyOctalEscapeSequence
+                let mut value = c.to_digit(8).unwrap();
+                let max_len = if value < 4 { 3 } else { 2 };
+                for _ in 1..max_len {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            self.next();
+                            value = value * 8 + digit;
+                        },
+                        None => break,
+                    }
+                }
+                value
+            },
+            'x' => match self.parse_hex(2) {
+                Some(value) => value,
+                None if self.unicode => {
+                    self.pos = start;
+                    return self.error("invalid escape");
+                },
+                None => 'x' as u32,
+            },
+            'u' => match self.parse_unicode_escape(self.unicode) {
+                Some(value) => value,
+                None if self.unicode => {
+                    self.pos = start;
+                    return self.error("invalid unicode escape");
+                },
+                None => 'u' as u32,
+            },
+            _ if self.unicode => {
+                let valid = SYNTAX_CHARACTERS.contains(c) || c == '/' ||
+                    (self.unicode_sets && in_class && CLASS_SET_RESERVED_PUNCTUATORS.contains(c));
+                if !valid {
+                    self.pos = start;
+                    return self.error("invalid escape");
+                }
+                c as u32
+            },
+            // Annex B: `\k` is an identity escape only without named groups
+            'k' if self.named_groups => {
+                self.pos = start;
+                return self.error("invalid named reference");
+            },
+            _ => {
+                let end = self.pos;
+                self.char_units(c, |high| terms.push(Node { kind: NodeKind::Char(high), start, end }))
+            },
+        };
+        Ok(value)
+    }
+
+    /// Parses `{name}` or `{name=value}` after `\p` or `\P`.
+    fn parse_property(&mut self, negated: bool, start: usize) -> Result<ClassEscape, RegexpError> {
+        if !self.eat("{") {
+            self.pos = start;
+            return self.error("invalid property name");
+        }
+        let name = self.parse_property_word();
+        let value = if self.eat("=") { Some(self.parse_property_word()) } else { None };
+        if !self.eat("}") {
+            self.pos = start;
+            return self.error("invalid property name");
+        }
+
+        let valid = match value {
+            Some(ref value) => match &name[..] {
+                "General_Category" | "gc" => GENERAL_CATEGORY_VALUES.contains(&&value[..]),
+                "Script" | "sc" | "Script_Extensions" | "scx" => !value.is_empty(),
+                _ => false,
+            },
+            None => {
+                if PROPERTIES_OF_STRINGS.contains(&&name[..]) {
+                    if !self.unicode_sets || negated {
+                        self.pos = start;
+                        return self.error("invalid property name");
+                    }
+                    true
+                } else {
+                    GENERAL_CATEGORY_VALUES.contains(&&name[..]) || BINARY_PROPERTIES.contains(&&name[..])
+                }
+            },
+        };
+        if !valid {
+            self.pos = start;
+            return self.error("invalid property name");
+        }
+        Ok(ClassEscape::Property { name, value, negated })
+    }
+
+    fn parse_property_word(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.next();
+        }
+        self.src[start..self.pos].to_owned()
+    }
+
+    fn parse_class(&mut self) -> Result<CharacterClass, RegexpError> {
+        let start = self.pos;
+        self.next();
+        let negated = self.eat("^");
+        let class = if self.unicode_sets {
+            self.parse_class_set(negated, start)?
+        } else {
+            self.parse_class_ranges(negated, start)?
+        };
+        if class.negated && class.may_contain_strings() {
+            self.pos = start;
+            return self.error("negated character class may contain strings");
+        }
+        Ok(class)
+    }
+
+    fn parse_class_ranges(&mut self, negated: bool, start: usize) -> Result<CharacterClass, RegexpError> {
+        let mut items = Vec::new();
+        loop {
+            if self.eat("]") {
+                break;
+            }
+            let atom_start = self.pos;
+            let (high, from) = self.parse_class_atom(start)?;
+            items.extend(high.map(ClassItem::Char));
+            if !self.looking_at("-") || self.peek_at(1).is_none_or(|c| c == ']') {
+                items.push(class_item(from));
+                continue;
+            }
+            self.next();
+            // an astral character ends a range with its high surrogate, the low one follows the range
+            let (to, low) = match self.parse_class_atom(start)? {
+                (Some(high), low) => (ClassAtom::Char(high), Some(low)),
+                (None, to) => (to, None),
+            };
+            match (from, to) {
+                (ClassAtom::Char(from), ClassAtom::Char(to)) => {
+                    if from > to {
+                        self.pos = atom_start;
+                        return self.error("range out of order in character class");
+                    }
+                    items.push(ClassItem::Range(from, to));
+                },
+                _ if self.unicode => {
+                    self.pos = atom_start;
+                    return self.error("invalid character class range");
+                },
+                (from, to) => {
+                    // Annex B: `[\d-z]` is `\d`, `-` and `z`
+                    items.push(class_item(from));
+                    items.push(ClassItem::Char('-' as u32));
+                    items.push(class_item(to));
+                },
+            }
+            items.extend(low.map(class_item));
+        }
+        Ok(CharacterClass { negated, op: ClassSetOp::Union, items })
+    }
+
+    /// One ClassAtom; outside of unicode mode an astral character is returned as its high
+    /// surrogate and the atom of its low one.
+    fn parse_class_atom(&mut self, class_start: usize) -> Result<(Option<u32>, ClassAtom), RegexpError> {
+        let start = self.pos;
+        let mut high = None;
+        let mut terms = Vec::new();
+        let atom = match self.next() {
+            None => {
+                self.pos = class_start;
+                return self.error("unterminated character class");
+            },
+            Some('\\') => self.parse_class_escape(true, &mut terms, start)?,
+            Some(c) => ClassAtom::Char(self.char_units(c, |unit| high = Some(unit))),
+        };
+        for term in terms {
+            if let NodeKind::Char(unit) = term.kind {
+                high = Some(unit);
+            }
+        }
+        Ok((high, atom))
+    }
+
+    /// The contents of a `v` mode class after `[` or `[^`, up to and including `]`.
+    fn parse_class_set(&mut self, negated: bool, start: usize) -> Result<CharacterClass, RegexpError> {
+        let mut items = Vec::new();
+        let mut op = ClassSetOp::Union;
+        loop {
+            if self.eat("]") {
+                break;
+            }
+            if self.peek().is_none() {
+                self.pos = start;
+                return self.error("unterminated character class");
+            }
+
+            let op_start = self.pos;
+            let next_op = if self.eat("&&") {
+                Some(ClassSetOp::Intersection)
+            } else if self.eat("--") {
+                Some(ClassSetOp::Subtraction)
+            } else {
+                None
+            };
+            match next_op {
+                Some(next_op) => {
+                    let is_first = items.len() == 1 && op == ClassSetOp::Union;
+                    if !(is_first || op == next_op) || matches!(items.last(), Some(&ClassItem::Range(..))) || items.is_empty() {
+                        self.pos = op_start;
+                        return self.error("invalid set operation in character class");
+                    }
+                    if self.looking_at("&") {
+                        return self.error("invalid set operation in character class");
+                    }
+                    op = next_op;
+                    let operand = self.parse_class_set_operand(start)?;
+                    if let ClassItem::Range(..) = operand {
+                        self.pos = op_start;
+                        return self.error("invalid set operation in character class");
+                    }
+                    items.push(operand);
+                },
+                None if op != ClassSetOp::Union => {
+                    return self.error("invalid set operation in character class");
+                },
+                None => {
+                    let operand = self.parse_class_set_operand(start)?;
+                    items.push(operand);
+                },
+            }
+        }
+        Ok(CharacterClass { negated, op, items })
+    }
+
+    /// A nested class, `\q{...}`, a class escape, a character or a range of characters.
+    fn parse_class_set_operand(&mut self, class_start: usize) -> Result<ClassItem, RegexpError> {
+        let start = self.pos;
+        if self.looking_at("[") {
+            return self.parse_class().map(ClassItem::Class);
+        }
+        if self.eat("\\q{") {
+            return self.parse_class_strings(start);
+        }
+        let from = match self.parse_class_set_character(class_start)? {
+            ClassAtom::Char(c) => c,
+            ClassAtom::Escape(escape) => return Ok(ClassItem::Escape(escape)),
+        };
+        if !self.looking_at("-") || self.looking_at("--") {
+            return Ok(ClassItem::Char(from));
+        }
+        self.next();
+        match self.parse_class_set_character(class_start)? {
+            ClassAtom::Char(to) if from <= to => Ok(ClassItem::Range(from, to)),
+            ClassAtom::Char(_) => {
+                self.pos = start;
+                self.error("range out of order in character class")
+            },
+            ClassAtom::Escape(_) => {
+                self.pos = start;
+                self.error("invalid character class range")
+            },
+        }
+    }
+
+    fn parse_class_set_character(&mut self, class_start: usize) -> Result<ClassAtom, RegexpError> {
+        let start = self.pos;
+        let c = match self.peek() {
+            Some(c) => c,
+            None => {
+                self.pos = class_start;
+                return self.error("unterminated character class");
+            },
+        };
+        if c == '\\' {
+            self.next();
+            return self.parse_class_escape(true, &mut Vec::new(), start);
+        }
+        let doubled = self.peek_at(1) == Some(c);
+        if CLASS_SET_SYNTAX_CHARACTERS.contains(c) || (doubled && CLASS_SET_RESERVED_DOUBLE_PUNCTUATORS.contains(c)) {
+            return self.error("invalid character in character class");
+        }
+        self.next();
+        Ok(ClassAtom::Char(c as u32))
+    }
+
+    /// The strings of `\q{a|bc}` after the `{`.
+    fn parse_class_strings(&mut self, start: usize) -> Result<ClassItem, RegexpError> {
+        let mut strings = vec![Vec::new()];
+        loop {
+            match self.peek() {
+                Some('}') => {
+                    self.next();
+                    break;
+                },
+                Some('|') => {
+                    self.next();
+                    strings.push(Vec::new());
+                },
+                Some('\\') => {
+                    let escape_start = self.pos;
+                    self.next();
+                    let c = self.next().unwrap_or('\\');
+                    let value = self.parse_character_escape(c, true, &mut Vec::new(), escape_start)?;
+                    strings.last_mut().unwrap().push(value);
+                },
+                Some(c) => {
+                    let doubled = self.peek_at(1) == Some(c);
+                    if CLASS_SET_SYNTAX_CHARACTERS.contains(c) || (doubled && CLASS_SET_RESERVED_DOUBLE_PUNCTUATORS.contains(c)) {
+                        return self.error("invalid character in character class");
+                    }
+                    self.next();
+                    strings.last_mut().unwrap().push(c as u32);
+                },
+                None => {
+                    self.pos = start;
+                    return self.error("unterminated class string disjunction");
+                },
+            }
+        }
+        Ok(ClassItem::Strings(strings))
+    }
+}
+
+fn class_item(atom: ClassAtom) -> ClassItem {
+    match atom {
+        ClassAtom::Char(c) => ClassItem::Char(c),
+        ClassAtom::Escape(escape) => ClassItem::Escape(escape),
+    }
+}
+
+const GENERAL_CATEGORY_VALUES: &[&str] = &[
+    "C", "Other", "Cc", "Control", "cntrl", "Cf", "Format", "Cn", "Unassigned", "Co", "Private_Use",
+    "Cs", "Surrogate", "L", "Letter", "LC", "Cased_Letter", "Ll", "Lowercase_Letter", "Lm",
+    "Modifier_Letter", "Lo", "Other_Letter", "Lt", "Titlecase_Letter", "Lu", "Uppercase_Letter",
+    "M", "Mark", "Combining_Mark", "Mc", "Spacing_Mark", "Me", "Enclosing_Mark", "Mn",
+    "Nonspacing_Mark", "N", "Number", "Nd", "Decimal_Number", "digit", "Nl", "Letter_Number", "No",
+    "Other_Number", "P", "Punctuation", "punct", "Pc", "Connector_Punctuation", "Pd",
+    "Dash_Punctuation", "Pe", "Close_Punctuation", "Pf", "Final_Punctuation", "Pi",
+    "Initial_Punctuation", "Po", "Other_Punctuation", "Ps", "Open_Punctuation", "S", "Symbol", "Sc",
+    "Currency_Symbol", "Sk", "Modifier_Symbol", "Sm", "Math_Symbol", "So", "Other_Symbol", "Z",
+    "Separator", "Zl", "Line_Separator", "Zp", "Paragraph_Separator", "Zs", "Space_Separator",
+];
+
+const BINARY_PROPERTIES: &[&str] = &[
+    "ASCII", "ASCII_Hex_Digit", "AHex", "Alphabetic", "Alpha", "Any", "Assigned", "Bidi_Control",
+    "Bidi_C", "Bidi_Mirrored", "Bidi_M", "Case_Ignorable", "CI", "Cased", "Changes_When_Casefolded",
+    "CWCF", "Changes_When_Casemapped", "CWCM", "Changes_When_Lowercased", "CWL",
+    "Changes_When_NFKC_Casefolded", "CWKCF", "Changes_When_Titlecased", "CWT",
+    "Changes_When_Uppercased", "CWU", "Dash", "Default_Ignorable_Code_Point", "DI", "Deprecated",
+    "Dep", "Diacritic", "Dia", "Emoji", "Emoji_Component", "EComp", "Emoji_Modifier", "EMod",
+    "Emoji_Modifier_Base", "EBase", "Emoji_Presentation", "EPres", "Extended_Pictographic",
+    "ExtPict", "Extender", "Ext", "Grapheme_Base", "Gr_Base", "Grapheme_Extend", "Gr_Ext",
+    "Hex_Digit", "Hex", "IDS_Binary_Operator", "IDSB", "IDS_Trinary_Operator", "IDST",
+    "ID_Continue", "IDC", "ID_Start", "IDS", "Ideographic", "Ideo", "Join_Control", "Join_C",
+    "Logical_Order_Exception", "LOE", "Lowercase", "Lower", "Math", "Noncharacter_Code_Point",
+    "NChar", "Pattern_Syntax", "Pat_Syn", "Pattern_White_Space", "Pat_WS", "Quotation_Mark",
+    "QMark", "Radical", "Regional_Indicator", "RI", "Sentence_Terminal", "STerm", "Soft_Dotted",
+    "SD", "Terminal_Punctuation", "Term", "Unified_Ideograph", "UIdeo", "Uppercase", "Upper",
+    "Variation_Selector", "VS", "White_Space", "space", "XID_Continue", "XIDC", "XID_Start", "XIDS",
+];
+
+const PROPERTIES_OF_STRINGS: &[&str] = &[
+    "Basic_Emoji", "Emoji_Keycap_Sequence", "RGI_Emoji_Modifier_Sequence", "RGI_Emoji_Flag_Sequence",
+    "RGI_Emoji_Tag_Sequence", "RGI_Emoji_ZWJ_Sequence", "RGI_Emoji",
+];
+
+impl JsToken {
+    /// For a `RegexpLiteral`, the parsed pattern; see `parse_regexp`.
+    pub fn parse_regexp(&self) -> Option<Result<Pattern, RegexpError>> {
+        self.as_token_ref().parse_regexp()
+    }
+}
+
+impl<'a> JsTokenRef<'a> {
+    /// For a `RegexpLiteral`, the parsed pattern; see `parse_regexp`.
+    pub fn parse_regexp(&self) -> Option<Result<Pattern, RegexpError>> {
+        match *self {
+            JsTokenRef::RegexpLiteral(pattern, flags) => Some(parse_regexp(pattern, flags)),
+            _ => None,
+        }
+    }
+}
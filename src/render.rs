@@ -0,0 +1,152 @@
+use diagnostic::{Diagnostic, Severity};
+use line_index::{LineIndex, SourceLine};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Formats diagnostics with a code frame:
+///
+/// ```text
+/// app.js:1:5: error[unterminated-string-literal]: unterminated string literal
+///   |
+/// 1 | a = 'abc
+///   |     ^^^^
+/// ```
+///
+/// Colors are ANSI escapes, off by default so that the output can go to a log.
+pub struct Renderer<'a> {
+    file_name: &'a str,
+    source: Source<'a>,
+    color: bool,
+}
+
+enum Source<'a> {
+    Text(LineIndex<'a>),
+    // sorted by offset
+    Lines(&'a [SourceLine]),
+}
+
+/// Where a diagnostic starts.
+struct Location<'a> {
+    offset: usize,
+    line: usize,
+    column: usize,
+    line_start: usize,
+    line_text: &'a str,
+}
+
+impl<'a> Renderer<'a> {
+    /// A plain renderer for diagnostics of `src`, which is shown as `file_name`.
+    pub fn new(file_name: &'a str, src: &'a str) -> Self {
+        Renderer {
+            file_name,
+            source: Source::Text(LineIndex::new(src)),
+            color: false,
+        }
+    }
+
+    /// A renderer that only has some lines of the source, e.g. from a `LineCollector`.
+    /// Diagnostics starting on other lines are shown as by `without_source`.
+    pub fn with_lines(file_name: &'a str, lines: &'a [SourceLine]) -> Self {
+        Renderer {
+            file_name,
+            source: Source::Lines(lines),
+            color: false,
+        }
+    }
+
+    /// A renderer for when the source cannot be read. Diagnostics are shown at their byte
+    /// offset, without a code frame:
+    ///
+    /// ```text
+    /// app.js: byte 4: error[unterminated-string-literal]: unterminated string literal
+    /// ```
+    pub fn without_source(file_name: &'a str) -> Self {
+        Renderer::with_lines(file_name, &[])
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The diagnostic as text, ending with a line break.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity_color = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+        let start = match self.locate(diagnostic.span.start) {
+            Some(start) => start,
+            None => {
+                let location = format!("{}: byte {}", self.file_name, diagnostic.span.start);
+                return self.render_message(&location, diagnostic, severity_color);
+            },
+        };
+
+        let location = format!("{}:{}:{}", self.file_name, start.line, start.column);
+        let mut out = self.render_message(&location, diagnostic, severity_color);
+
+        let line_number = start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line_text = start.line_text;
+        let (before, rest) = line_text.split_at((start.offset - start.line_start).min(line_text.len()));
+        // a span going past the line is underlined up to the line end
+        let end_in_line = diagnostic.span.end.saturating_sub(start.line_start) <= line_text.len();
+        let underlined = if end_in_line { rest.get(..diagnostic.span.end.saturating_sub(start.offset)).unwrap_or(rest) } else { rest };
+        // tabs are kept, so that the carets line up whatever the tab width of the terminal
+        let indent: String = before.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let width = underlined.chars().count().max(1);
+
+        out += &format!("{} {}\n", gutter, self.paint(BLUE, "|"));
+        out += &format!("{} {} {}\n", self.paint(BLUE, &line_number), self.paint(BLUE, "|"), line_text);
+        out += &format!("{} {} {}{}\n", gutter, self.paint(BLUE, "|"), indent, self.paint(severity_color, &"^".repeat(width)));
+        out
+    }
+
+    fn locate(&self, offset: usize) -> Option<Location<'_>> {
+        match self.source {
+            Source::Text(ref index) => {
+                let position = index.position(offset);
+                Some(Location {
+                    offset: position.offset,
+                    line: position.line,
+                    column: position.column,
+                    line_start: index.offset(position.line, 1).unwrap_or(position.offset),
+                    line_text: index.line_text(position.line).unwrap_or(""),
+                })
+            },
+            Source::Lines(lines) => {
+                let line = lines[..lines.partition_point(|x| x.offset <= offset)].last()?;
+                let before = line.text.get(..offset - line.offset)?;
+                Some(Location {
+                    offset,
+                    line: line.line,
+                    column: before.chars().count() + 1,
+                    line_start: line.offset,
+                    line_text: &line.text,
+                })
+            },
+        }
+    }
+
+    /// The first line of a diagnostic, ending with a line break.
+    fn render_message(&self, location: &str, diagnostic: &Diagnostic, severity_color: &str) -> String {
+        let mut out = format!("{}: ", location);
+        out += &self.paint(severity_color, &format!("{}[{}]", diagnostic.severity.as_str(), diagnostic.code.as_str()));
+        out += &self.paint(BOLD, &format!(": {}", diagnostic.message));
+        out.push('\n');
+        out
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_owned()
+        }
+    }
+}
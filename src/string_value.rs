@@ -0,0 +1,155 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use {is_line_terminator, JsToken, JsTokenRef};
+
+/// The cooked value of a string literal, in UTF-16 code units.
+///
+/// Lone surrogates written as `\uD800` are kept, so this is WTF-16 rather than valid UTF-16.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StringValue {
+    pub utf16: Vec<u16>,
+    /// A legacy octal escape (`\01`) or `\8`/`\9` was used; these are errors in strict mode.
+    pub legacy_octal: bool,
+}
+
+impl StringValue {
+    /// The value as a `String`, with lone surrogates replaced by U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.utf16)
+    }
+}
+
+/// Decodes the raw text of a string literal, quotes included.
+/// Returns `None` if the literal is unterminated or has a malformed escape.
+pub fn string_value(raw: &str) -> Option<StringValue> {
+    let mut chars = raw.chars();
+    let quote = chars.next()?;
+    if quote != '\'' && quote != '"' {
+        return None;
+    }
+
+    let mut result = StringValue {
+        utf16: Vec::with_capacity(raw.len()),
+        legacy_octal: false,
+    };
+    let mut chars = chars.peekable();
+    loop {
+        match chars.next()? {
+            c if c == quote => break,
+            '\\' => cook_escape(&mut chars, &mut result)?,
+            // U+2028 and U+2029 are allowed in string literals, LF and CR are not
+            '\n' | '\r' => return None,
+            c => push_char(&mut result.utf16, c),
+        }
+    }
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(result)
+}
+
+fn push_char(utf16: &mut Vec<u16>, c: char) {
+    let mut buf = [0; 2];
+    utf16.extend_from_slice(c.encode_utf16(&mut buf));
+}
+
+fn cook_escape(chars: &mut Peekable<Chars>, result: &mut StringValue) -> Option<()> {
+    let c = chars.next()?;
+    match c {
+        'b' => result.utf16.push(0x08),
+        'f' => result.utf16.push(0x0c),
+        'n' => result.utf16.push(0x0a),
+        'r' => result.utf16.push(0x0d),
+        't' => result.utf16.push(0x09),
+        'v' => result.utf16.push(0x0b),
+        'x' => {
+            let value = hex_digits(chars, 2)?;
+            result.utf16.push(value as u16);
+        },
+        'u' if chars.peek() == Some(&'{') => {
+            chars.next();
+            let mut value = 0u32;
+            let mut count = 0;
+            loop {
+                match chars.next()? {
+                    '}' if count > 0 => break,
+                    c => {
+                        value = value * 16 + c.to_digit(16)?;
+                        if value > 0x10ffff {
+                            return None;
+                        }
+                        count += 1;
+                    },
+                }
+            }
+            push_code_point(&mut result.utf16, value);
+        },
+        'u' => {
+            let value = hex_digits(chars, 4)?;
+            result.utf16.push(value as u16);
+        },
+        '0' if !chars.peek().is_some_and(|c| c.is_ascii_digit()) => result.utf16.push(0),
+        '0'..='7' => {
+            // LegacyOctalEscapeSequence: up to three digits for values below 0o400
+            let mut value = c.to_digit(8).unwrap();
+            let max_len = if value < 4 { 3 } else { 2 };
+            for _ in 1..max_len {
+                match chars.peek().and_then(|c| c.to_digit(8)) {
+                    Some(digit) => {
+                        chars.next();
+                        value = value * 8 + digit;
+                    },
+                    None => break,
+                }
+            }
+            result.utf16.push(value as u16);
+            result.legacy_octal = true;
+        },
+        '8' | '9' => {
+            result.utf16.push(c as u16);
+            result.legacy_octal = true;
+        },
+        '\r' => {
+            // LineContinuation, `\r\n` is a single line terminator
+            if chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+        },
+        _ if is_line_terminator(c) => {},
+        _ => push_char(&mut result.utf16, c),
+    }
+    Some(())
+}
+
+fn hex_digits(chars: &mut Peekable<Chars>, count: usize) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..count {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(value)
+}
+
+fn push_code_point(utf16: &mut Vec<u16>, value: u32) {
+    match std::char::from_u32(value) {
+        Some(c) => push_char(utf16, c),
+        // a surrogate code point
+        None => utf16.push(value as u16),
+    }
+}
+
+impl JsToken {
+    /// For a `StringLiteral`, the cooked value; see `string_value`.
+    pub fn string_value(&self) -> Option<StringValue> {
+        self.as_token_ref().string_value()
+    }
+}
+
+impl<'a> JsTokenRef<'a> {
+    /// For a `StringLiteral`, the cooked value; see `string_value`.
+    pub fn string_value(&self) -> Option<StringValue> {
+        match *self {
+            JsTokenRef::StringLiteral(raw) => string_value(raw),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::borrow::Cow;
+use {JsToken, JsTokenRef};
+
+/// ReservedWord, except `let` and `static` which are only reserved in strict mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Keyword {
+    Await,
+    Break,
+    Case,
+    Catch,
+    Class,
+    Const,
+    Continue,
+    Debugger,
+    Default,
+    Delete,
+    Do,
+    Else,
+    Enum,
+    Export,
+    Extends,
+    False,
+    Finally,
+    For,
+    Function,
+    If,
+    Import,
+    In,
+    Instanceof,
+    New,
+    Null,
+    Return,
+    Super,
+    Switch,
+    This,
+    Throw,
+    True,
+    Try,
+    Typeof,
+    Var,
+    Void,
+    While,
+    With,
+    Yield,
+}
+
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("await", Keyword::Await),
+    ("break", Keyword::Break),
+    ("case", Keyword::Case),
+    ("catch", Keyword::Catch),
+    ("class", Keyword::Class),
+    ("const", Keyword::Const),
+    ("continue", Keyword::Continue),
+    ("debugger", Keyword::Debugger),
+    ("default", Keyword::Default),
+    ("delete", Keyword::Delete),
+    ("do", Keyword::Do),
+    ("else", Keyword::Else),
+    ("enum", Keyword::Enum),
+    ("export", Keyword::Export),
+    ("extends", Keyword::Extends),
+    ("false", Keyword::False),
+    ("finally", Keyword::Finally),
+    ("for", Keyword::For),
+    ("function", Keyword::Function),
+    ("if", Keyword::If),
+    ("import", Keyword::Import),
+    ("in", Keyword::In),
+    ("instanceof", Keyword::Instanceof),
+    ("new", Keyword::New),
+    ("null", Keyword::Null),
+    ("return", Keyword::Return),
+    ("super", Keyword::Super),
+    ("switch", Keyword::Switch),
+    ("this", Keyword::This),
+    ("throw", Keyword::Throw),
+    ("true", Keyword::True),
+    ("try", Keyword::Try),
+    ("typeof", Keyword::Typeof),
+    ("var", Keyword::Var),
+    ("void", Keyword::Void),
+    ("while", Keyword::While),
+    ("with", Keyword::With),
+    ("yield", Keyword::Yield),
+];
+
+impl Keyword {
+    pub fn from_name(name: &str) -> Option<Keyword> {
+        KEYWORDS.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        KEYWORDS.iter().find(|x| x.1 == self).unwrap().0
+    }
+}
+
+/// Identifiers that act as keywords in some contexts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ContextualKeyword {
+    Let,
+    Static,
+    Async,
+    Of,
+    Get,
+    Set,
+}
+
+const CONTEXTUAL_KEYWORDS: &[(&str, ContextualKeyword)] = &[
+    ("let", ContextualKeyword::Let),
+    ("static", ContextualKeyword::Static),
+    ("async", ContextualKeyword::Async),
+    ("of", ContextualKeyword::Of),
+    ("get", ContextualKeyword::Get),
+    ("set", ContextualKeyword::Set),
+];
+
+impl ContextualKeyword {
+    pub fn from_name(name: &str) -> Option<ContextualKeyword> {
+        CONTEXTUAL_KEYWORDS.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        CONTEXTUAL_KEYWORDS.iter().find(|x| x.1 == self).unwrap().0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Punctuator {
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Ellipsis,
+    Semicolon,
+    Comma,
+    Less,
+    Greater,
+    LessEq,
+    GreaterEq,
+    Eq,
+    NotEq,
+    StrictEq,
+    StrictNotEq,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    StarStar,
+    PlusPlus,
+    MinusMinus,
+    Shl,
+    Shr,
+    UShr,
+    Amp,
+    Pipe,
+    Caret,
+    Bang,
+    Tilde,
+    AmpAmp,
+    PipePipe,
+    QuestionQuestion,
+    Question,
+    QuestionDot,
+    Colon,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
+    StarStarAssign,
+    ShlAssign,
+    ShrAssign,
+    UShrAssign,
+    AmpAssign,
+    PipeAssign,
+    CaretAssign,
+    AmpAmpAssign,
+    PipePipeAssign,
+    QuestionQuestionAssign,
+    Arrow,
+}
+
+const PUNCTUATORS: &[(&str, Punctuator)] = &[
+    ("{", Punctuator::LBrace),
+    ("}", Punctuator::RBrace),
+    ("(", Punctuator::LParen),
+    (")", Punctuator::RParen),
+    ("[", Punctuator::LBracket),
+    ("]", Punctuator::RBracket),
+    (".", Punctuator::Dot),
+    ("...", Punctuator::Ellipsis),
+    (";", Punctuator::Semicolon),
+    (",", Punctuator::Comma),
+    ("<", Punctuator::Less),
+    (">", Punctuator::Greater),
+    ("<=", Punctuator::LessEq),
+    (">=", Punctuator::GreaterEq),
+    ("==", Punctuator::Eq),
+    ("!=", Punctuator::NotEq),
+    ("===", Punctuator::StrictEq),
+    ("!==", Punctuator::StrictNotEq),
+    ("+", Punctuator::Plus),
+    ("-", Punctuator::Minus),
+    ("*", Punctuator::Star),
+    ("/", Punctuator::Slash),
+    ("%", Punctuator::Percent),
+    ("**", Punctuator::StarStar),
+    ("++", Punctuator::PlusPlus),
+    ("--", Punctuator::MinusMinus),
+    ("<<", Punctuator::Shl),
+    (">>", Punctuator::Shr),
+    (">>>", Punctuator::UShr),
+    ("&", Punctuator::Amp),
+    ("|", Punctuator::Pipe),
+    ("^", Punctuator::Caret),
+    ("!", Punctuator::Bang),
+    ("~", Punctuator::Tilde),
+    ("&&", Punctuator::AmpAmp),
+    ("||", Punctuator::PipePipe),
+    ("??", Punctuator::QuestionQuestion),
+    ("?", Punctuator::Question),
+    ("?.", Punctuator::QuestionDot),
+    (":", Punctuator::Colon),
+    ("=", Punctuator::Assign),
+    ("+=", Punctuator::PlusAssign),
+    ("-=", Punctuator::MinusAssign),
+    ("*=", Punctuator::StarAssign),
+    ("/=", Punctuator::SlashAssign),
+    ("%=", Punctuator::PercentAssign),
+    ("**=", Punctuator::StarStarAssign),
+    ("<<=", Punctuator::ShlAssign),
+    (">>=", Punctuator::ShrAssign),
+    (">>>=", Punctuator::UShrAssign),
+    ("&=", Punctuator::AmpAssign),
+    ("|=", Punctuator::PipeAssign),
+    ("^=", Punctuator::CaretAssign),
+    ("&&=", Punctuator::AmpAmpAssign),
+    ("||=", Punctuator::PipePipeAssign),
+    ("??=", Punctuator::QuestionQuestionAssign),
+    ("=>", Punctuator::Arrow),
+];
+
+impl Punctuator {
+    pub fn from_text(s: &str) -> Option<Punctuator> {
+        PUNCTUATORS.iter().find(|x| x.0 == s).map(|x| x.1)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        PUNCTUATORS.iter().find(|x| x.1 == self).unwrap().0
+    }
+}
+
+/// Typed view of a token. Words are split into keywords and identifiers,
+/// punctuation is parsed into `Punctuator`; other tokens are passed through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypedToken<'a> {
+    Keyword(Keyword),
+    Identifier {
+        name: Cow<'a, str>,
+        contextual: Option<ContextualKeyword>,
+    },
+    Punctuator(Punctuator),
+    Other(&'a JsToken),
+}
+
+impl JsToken {
+    pub fn typed(&self) -> TypedToken<'_> {
+        match *self {
+            JsToken::Word(_) => {
+                let name = self.identifier_name().unwrap();
+                match Keyword::from_name(&name) {
+                    Some(keyword) => TypedToken::Keyword(keyword),
+                    None => {
+                        let contextual = ContextualKeyword::from_name(&name);
+                        TypedToken::Identifier { name, contextual }
+                    },
+                }
+            },
+            JsToken::Punctuation(ref p) => match Punctuator::from_text(p) {
+                Some(p) => TypedToken::Punctuator(p),
+                None => TypedToken::Other(self),
+            },
+            _ => TypedToken::Other(self),
+        }
+    }
+
+    /// The reserved word this token is, if any. Escaped words like `\u0069f` are decoded first.
+    pub fn keyword(&self) -> Option<Keyword> {
+        self.as_token_ref().keyword()
+    }
+
+    pub fn contextual_keyword(&self) -> Option<ContextualKeyword> {
+        self.as_token_ref().contextual_keyword()
+    }
+
+    pub fn punctuator(&self) -> Option<Punctuator> {
+        self.as_token_ref().punctuator()
+    }
+}
+
+impl<'a> JsTokenRef<'a> {
+    /// The reserved word this token is, if any. Escaped words like `\u0069f` are decoded first.
+    pub fn keyword(&self) -> Option<Keyword> {
+        Keyword::from_name(&self.identifier_name()?)
+    }
+
+    pub fn contextual_keyword(&self) -> Option<ContextualKeyword> {
+        ContextualKeyword::from_name(&self.identifier_name()?)
+    }
+
+    pub fn punctuator(&self) -> Option<Punctuator> {
+        match *self {
+            JsTokenRef::Punctuation(p) => Punctuator::from_text(p),
+            _ => None,
+        }
+    }
+}
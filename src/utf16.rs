@@ -0,0 +1,114 @@
+use std::char::{decode_utf16, DecodeUtf16, REPLACEMENT_CHARACTER};
+use std::iter::Cloned;
+use std::slice;
+use diagnostic::{Diagnostic, DiagnosticCode};
+use {JsToken, Span, SpannedTokenIterator};
+
+/// Decodes UTF-16, replacing lone surrogates with U+FFFD. Both take one code unit,
+/// so the UTF-16 offsets of the lexer stay exact.
+pub struct Utf16Chars<'a> {
+    inner: DecodeUtf16<Cloned<slice::Iter<'a, u16>>>,
+}
+
+impl<'a> Utf16Chars<'a> {
+    pub fn new(src: &'a [u16]) -> Self {
+        Utf16Chars {
+            inner: decode_utf16(src.iter().cloned()),
+        }
+    }
+}
+
+impl<'a> Iterator for Utf16Chars<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        self.inner.next().map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+    }
+}
+
+/// A token lexed from UTF-16 source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utf16Token<'a> {
+    /// The token, with lone surrogates replaced by U+FFFD.
+    pub kind: JsToken,
+    /// The exact code units of the token, lone surrogates included.
+    pub text: &'a [u16],
+    /// Use `start_utf16` and `end_utf16`; `start` and `end` are offsets in the
+    /// UTF-8 text with lone surrogates replaced.
+    pub span: Span,
+}
+
+pub struct Utf16TokenIterator<'a> {
+    src: &'a [u16],
+    inner: SpannedTokenIterator<Utf16Chars<'a>>,
+}
+
+impl<'a> Utf16TokenIterator<'a> {
+    pub(crate) fn new(src: &'a [u16], inner: SpannedTokenIterator<Utf16Chars<'a>>) -> Self {
+        Utf16TokenIterator { src, inner }
+    }
+
+    /// Problems found in the tokens returned so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.inner.diagnostics()
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.inner.take_diagnostics()
+    }
+
+    /// Reports the lone surrogates of a token that cannot hold them. A lone surrogate
+    /// on its own was already reported as an unexpected U+FFFD, which is replaced.
+    fn report_lone_surrogates(&mut self, token_span: Span, text: &[u16], first_new: usize) {
+        let diagnostics = &mut self.inner.inner.diagnostics;
+        let mut start = token_span.start;
+        let mut start_utf16 = token_span.start_utf16;
+        for c in decode_utf16(text.iter().cloned()) {
+            let c = match c {
+                Ok(c) => c,
+                Err(e) => {
+                    let span = Span {
+                        start,
+                        end: start + REPLACEMENT_CHARACTER.len_utf8(),
+                        start_utf16,
+                        end_utf16: start_utf16 + 1,
+                    };
+                    diagnostics.retain(|d| d.code != DiagnosticCode::UnexpectedCharacter || d.span != span);
+                    let mut diagnostic = Diagnostic::error(DiagnosticCode::LoneSurrogate, span);
+                    diagnostic.message = format!("lone surrogate U+{:04X} outside of a string, comment or template", e.unpaired_surrogate());
+                    diagnostics.push(diagnostic);
+                    REPLACEMENT_CHARACTER
+                },
+            };
+            start += c.len_utf8();
+            start_utf16 += c.len_utf16();
+        }
+        // keep the diagnostics of the token ordered by position
+        let first_new = first_new.min(diagnostics.len());
+        diagnostics[first_new..].sort_by_key(|d| d.span.start);
+    }
+}
+
+impl<'a> Iterator for Utf16TokenIterator<'a> {
+    type Item = Utf16Token<'a>;
+    fn next(&mut self) -> Option<Utf16Token<'a>> {
+        let first_new = self.inner.diagnostics().len();
+        let token = self.inner.next()?;
+        let text = &self.src[token.span.start_utf16..token.span.end_utf16];
+        match token.kind {
+            JsToken::StringLiteral(_) |
+            JsToken::TemplateLiteral(_) |
+            JsToken::TemplateHead(_) |
+            JsToken::TemplateMiddle(_) |
+            JsToken::TemplateTail(_) |
+            JsToken::LineComment(_) |
+            JsToken::MultilineComment(_) |
+            JsToken::Hashbang(_) => {},
+            _ => self.report_lone_surrogates(token.span, text, first_new),
+        }
+        Some(Utf16Token {
+            kind: token.kind,
+            text,
+            span: token.span,
+        })
+    }
+}
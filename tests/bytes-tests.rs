@@ -0,0 +1,96 @@
+extern crate js_lex_rs;
+extern crate proptest;
+
+mod common;
+
+use js_lex_rs::*;
+use js_lex_rs::diagnostic::Diagnostic;
+use common::js_fragment;
+use proptest::prelude::*;
+
+fn check_same(src: &str) {
+    let mut chars = tokenize_str(src);
+    let mut bytes = tokenize_bytes(src.as_bytes()).unwrap();
+    let char_tokens: Vec<JsToken> = chars.by_ref().collect();
+    let byte_tokens: Vec<JsToken> = bytes.by_ref().map(JsToken::from).collect();
+    assert_eq!(char_tokens, byte_tokens);
+    assert_eq!(chars.diagnostics(), bytes.diagnostics());
+}
+
+/// Pieces long enough for the eight-bytes-at-a-time scans.
+const LONG_PIECES: &[&str] = &[
+    "abcdefghijklmnop", "        ", "// a comment", "/* long*comment */", "'a longer string'",
+    "`template text`", "x\u{e9}y\u{1F600}zzzzzzzz", "0x1234_5678n", "\\u0061bcdefghij",
+];
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn same_as_chars(fragments in proptest::collection::vec(js_fragment(LONG_PIECES), 0..32)) {
+        check_same(&fragments.concat());
+    }
+}
+
+#[test]
+pub fn same_as_chars_jquery() {
+    check_same(include_str!("jquery-1.12.4.js"));
+    check_same(include_str!("jquery-1.12.4.min.js"));
+}
+
+#[test]
+pub fn stop_bytes() {
+    for src in &["'abcdefghijklmnop'", "'abcdefgh\nij'", "/*abcdefgh*/", "//abcdefghij\u{2028}x", "`abcdefgh${x}`", "\"abcdefg\u{e9}\""] {
+        check_same(src);
+    }
+}
+
+fn tokenize_bytes_no_whitespace(src: &str) -> Vec<JsTokenRef<'_>> {
+    tokenize_bytes(src.as_bytes())
+        .unwrap()
+        .filter(|x| !matches!(*x, JsTokenRef::Whitespace(_) | JsTokenRef::LineTerminator(_)))
+        .collect()
+}
+
+#[test]
+pub fn expected_tokens() {
+    use JsTokenRef::*;
+    assert_eq!(
+        vec![Word("x"), Punctuation("="), Word("a"), Punctuation("/"), NumberLiteral("2"), Punctuation("/"), Word("b")],
+        tokenize_bytes_no_whitespace("x = a / 2 / b"),
+    );
+    assert_eq!(
+        vec![Word("if"), Punctuation("("), Word("x"), Punctuation(")"), RegexpLiteral("[/]+", "g"), Punctuation("."), Word("test")],
+        tokenize_bytes_no_whitespace("if (x) /[/]+/g.test"),
+    );
+    assert_eq!(
+        vec![TemplateHead("`a${"), StringLiteral("'}'"), TemplateTail("}b`"), Punctuation("/"), NumberLiteral("2")],
+        tokenize_bytes_no_whitespace("`a${'}'}b` / 2"),
+    );
+    assert_eq!(
+        vec![LineComment("// \u{e9}t\u{e9}"), MultilineComment("/* a\n*/"), Word("caf\u{e9}"), Punctuation("?."), Word("x")],
+        tokenize_bytes_no_whitespace("// \u{e9}t\u{e9}\n/* a\n*/ caf\u{e9}?.x"),
+    );
+    assert_eq!(
+        vec![NumberLiteral("0x1F_FFn"), Punctuation(">>>="), StringLiteral("\"a\\\"b\""), Unknown("@")],
+        tokenize_bytes_no_whitespace("0x1F_FFn >>>= \"a\\\"b\" @"),
+    );
+}
+
+#[test]
+pub fn invalid_utf8() {
+    let err = tokenize_bytes(b"a = '\xff'").err().unwrap();
+    assert_eq!(5, err.valid_up_to());
+}
+
+#[test]
+pub fn offsets() {
+    let src = "let \u{e9} = '\u{1F600}'; @";
+    let diagnostics: Vec<Diagnostic> = {
+        let mut iter = tokenize_bytes(src.as_bytes()).unwrap();
+        iter.by_ref().for_each(drop);
+        iter.take_diagnostics()
+    };
+    assert_eq!((17, 18), (diagnostics[0].span.start, diagnostics[0].span.end));
+    assert_eq!((14, 15), (diagnostics[0].span.start_utf16, diagnostics[0].span.end_utf16));
+}
@@ -0,0 +1,18 @@
+use proptest::prelude::*;
+
+/// Characters and short pieces that mean something to the lexer, so that the inputs hit
+/// unterminated literals, comments, escapes and odd line terminators.
+const JS_PIECES: &[&str] = &[
+    "/", "*", "\\", "'", "\"", "`", "$", "${", "{", "}", "(", ")", "[", "]", ".", "?", "#", "#!", "!",
+    "=", ">", ">>>=", "=>", "<", "+", "-", ";", ",", ":", "0", "1", "9", "x", "n", "e", "_", "u", "a",
+    " ", "\t", "\n", "\r", "\r\n", "\u{2028}", "\u{2029}", "\u{a0}", "\u{feff}", "\u{e9}", "\u{1F600}",
+];
+
+/// A random piece of source: mostly one of the pieces above or `extra`, sometimes any character.
+pub fn js_fragment(extra: &'static [&'static str]) -> impl Strategy<Value = String> {
+    let pieces: Vec<&str> = JS_PIECES.iter().chain(extra).cloned().collect();
+    prop_oneof![
+        4 => proptest::sample::select(pieces).prop_map(|s| s.to_string()),
+        1 => any::<char>().prop_map(|c| c.to_string()),
+    ]
+}
@@ -0,0 +1,99 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::diagnostic::*;
+
+fn codes(src: &str) -> Vec<(DiagnosticCode, usize, usize)> {
+    tokenize_with_diagnostics(src).1.iter()
+        .map(|d| (d.code, d.span.start, d.span.end))
+        .collect()
+}
+
+#[test]
+pub fn no_diagnostics() {
+    let src = r#"let s = 'a\x41B\u{43}\
+'; /* c */ x = /[/]+/gu; `t${1e5 + 0x1_0n}`; ab\u{63};"#;
+    assert_eq!(Vec::<(DiagnosticCode, usize, usize)>::new(), codes(src));
+}
+
+#[test]
+pub fn unterminated() {
+    use DiagnosticCode::*;
+    assert_eq!(vec![(UnterminatedStringLiteral, 4, 8)], codes("a = 'abc"));
+    assert_eq!(vec![(UnterminatedTemplateLiteral, 0, 4)], codes("`abc"));
+    assert_eq!(vec![(UnterminatedTemplateLiteral, 4, 7)], codes("`${a}bc"));
+    assert_eq!(vec![(UnterminatedComment, 2, 8)], codes("a /* b *"));
+    assert_eq!(vec![(UnterminatedRegexp, 4, 7)], codes("x = /ab\n"));
+    assert_eq!(vec![(UnterminatedStringLiteral, 0, 2)], codes("'a\nb"));
+    assert_eq!(vec![(UnterminatedStringLiteral, 0, 2)], codes("'a\r\n"));
+}
+
+#[test]
+pub fn string_literals() {
+    use DiagnosticCode::*;
+    assert_eq!(vec![(InvalidHexEscape, 1, 4)], codes(r"'\x4g'"));
+    assert_eq!(vec![(InvalidUnicodeEscape, 1, 5)], codes(r"'\u12'"));
+    assert_eq!(vec![(InvalidUnicodeEscape, 1, 10)], codes(r"'\u{110000}'"));
+    assert_eq!(vec![(InvalidUnicodeEscape, 1, 6)], codes(r"'\u{41'"));
+    // templates may hold any escape, it only affects the cooked value
+    assert!(codes(r"`\x\u`").is_empty());
+}
+
+#[test]
+pub fn numeric_literals() {
+    use DiagnosticCode::*;
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 2)], codes("0b2"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 2)], codes("0x;"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 2)], codes("1e"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 3)], codes("1e+"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 1)], codes("3in x"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 1)], codes("1_"));
+    assert_eq!(vec![(InvalidNumericLiteral, 0, 3)], codes("017n"));
+}
+
+#[test]
+pub fn regexp_flags_and_identifiers() {
+    use DiagnosticCode::*;
+    assert_eq!(vec![(InvalidRegexpFlags, 7, 10)], codes("x = /a/gig"));
+    assert_eq!(vec![(InvalidRegexpFlags, 7, 9)], codes("x = /a/uv"));
+    assert_eq!(vec![(InvalidUnicodeEscape, 0, 6)], codes(r"\u0030abc"));
+    assert_eq!(vec![(InvalidUnicodeEscape, 1, 7)], codes(r"a\u{2F}"));
+}
+
+#[test]
+pub fn unexpected_character() {
+    let (tokens, diagnostics) = tokenize_with_diagnostics("a @ b");
+    assert_eq!(JsToken::Unknown("@".to_string()), tokens[2].kind);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(DiagnosticCode::UnexpectedCharacter, diagnostics[0].code);
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!("unexpected character '@'", diagnostics[0].message);
+    assert_eq!("unexpected-character", diagnostics[0].code.as_str());
+    assert_eq!((2, 3), (diagnostics[0].span.start, diagnostics[0].span.end));
+}
+
+#[test]
+pub fn tokens_unchanged() {
+    let src = "a = 'x\n0b2 /* ";
+    let (tokens, _) = tokenize_with_diagnostics(src);
+    let kinds: Vec<JsToken> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(tokenize(src), kinds);
+}
+
+#[test]
+pub fn jquery() {
+    let src = include_str!("jquery-1.12.4.js");
+    assert!(tokenize_with_diagnostics(src).1.is_empty());
+    let src = include_str!("jquery-1.12.4.min.js");
+    assert!(tokenize_with_diagnostics(src).1.is_empty());
+}
+
+#[test]
+pub fn iterator_diagnostics() {
+    let mut iter = tokenize_str("'a");
+    assert!(iter.diagnostics().is_empty());
+    iter.by_ref().for_each(drop);
+    assert_eq!(1, iter.diagnostics().len());
+    assert_eq!(1, iter.take_diagnostics().len());
+    assert!(iter.diagnostics().is_empty());
+}
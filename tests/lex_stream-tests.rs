@@ -1,6 +1,6 @@
 extern crate js_lex_rs;
 
-use js_lex_rs::lex_stream::LexStream;
+use js_lex_rs::lex_stream::{CharSource, LexStream};
 
 #[test]
 pub fn read() {
@@ -55,3 +55,88 @@ pub fn skip() {
     let c = s.read();
     assert_eq!(None, c);
 }
+
+#[test]
+pub fn offsets() {
+    let mut s = LexStream::new("a\u{e9}\u{1F600}b".chars());
+    assert_eq!((0, 0), (s.offset(), s.offset_utf16()));
+    s.lookahead(3);
+    assert_eq!((0, 0), (s.offset(), s.offset_utf16()));
+    s.read();
+    assert_eq!((1, 1), (s.offset(), s.offset_utf16()));
+    s.skip(2);
+    assert_eq!((7, 4), (s.offset(), s.offset_utf16()));
+    s.skip(2);
+    assert_eq!((8, 5), (s.offset(), s.offset_utf16()));
+}
+
+#[test]
+pub fn token_text() {
+    let mut s = LexStream::new("abc".chars());
+    s.read();
+    assert_eq!(Some('b'), s.lookahead(0));
+    assert_eq!("a", s.token_text());
+    s.start_token();
+    s.skip(2);
+    assert_eq!("bc", s.token_text());
+}
+
+#[test]
+pub fn long_lookahead() {
+    let src: String = (0..10000).map(|i| if i % 2 == 0 { 'a' } else { '\u{e9}' }).collect();
+    let mut s = LexStream::new(src.chars());
+    assert_eq!(Some('\u{e9}'), s.lookahead(9999));
+    assert_eq!(None, s.lookahead(10000));
+    s.skip(5000);
+    assert_eq!((7500, 5000), (s.offset(), s.offset_utf16()));
+    assert_eq!(Some('a'), s.lookahead(0));
+    assert_eq!(Some('\u{e9}'), s.lookahead(4999));
+    assert_eq!(None, s.lookahead(5000));
+}
+
+#[test]
+pub fn reset_to_mark() {
+    let mut s = LexStream::new("ab\u{1F600}cd".chars());
+    s.read();
+    let mark = s.mark();
+    s.skip(3);
+    assert_eq!((7, 5), (s.offset(), s.offset_utf16()));
+    assert_eq!("ab\u{1F600}c", s.token_text());
+    s.reset_to(mark);
+    assert_eq!((1, 1), (s.offset(), s.offset_utf16()));
+    assert_eq!("a", s.token_text());
+    assert_eq!(Some('b'), s.read());
+    assert_eq!(Some('\u{1F600}'), s.read());
+}
+
+#[test]
+pub fn release_mark() {
+    let mut s = LexStream::new("abc".chars());
+    let mark = s.mark();
+    s.skip(2);
+    s.release(mark);
+    assert_eq!(Some('c'), s.read());
+    assert_eq!(None, s.read());
+    assert_eq!((3, "abc"), (s.offset(), s.token_text()));
+}
+
+#[test]
+pub fn nested_marks() {
+    let src: String = "0123456789".repeat(100);
+    let mut s = LexStream::new(src.chars());
+    let outer = s.mark();
+    s.skip(10);
+    let inner = s.mark();
+    s.skip(500);
+    s.reset_to(inner);
+    assert_eq!(Some('0'), s.read());
+    let inner = s.mark();
+    s.skip(3);
+    s.release(inner);
+    assert_eq!(Some('4'), s.read());
+    s.reset_to(outer);
+    assert_eq!(0, s.offset());
+    s.skip(999);
+    assert_eq!(Some('9'), s.read());
+    assert_eq!(None, s.read());
+}
@@ -0,0 +1,136 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::line_index::*;
+
+#[test]
+pub fn line_breaks() {
+    let src = "a\r\nb\rc\nd\u{2028}e\u{2029}f";
+    let index = LineIndex::new(src);
+    assert_eq!(6, index.line_count());
+    assert_eq!(Some("a"), index.line_text(1));
+    assert_eq!(Some("b"), index.line_text(2));
+    assert_eq!(Some("d"), index.line_text(4));
+    assert_eq!(Some("f"), index.line_text(6));
+    assert_eq!(None, index.line_text(7));
+    assert_eq!(None, index.line_text(0));
+
+    let line_starts: Vec<_> = (1..7).map(|line| index.offset(line, 1).unwrap()).collect();
+    assert_eq!(vec![0, 3, 5, 7, 11, 15], line_starts);
+}
+
+#[test]
+pub fn crlf_is_one_line_break() {
+    let index = LineIndex::new("a\r\nb");
+    assert_eq!(2, index.line_count());
+    let p = index.position(2);
+    assert_eq!((1, 3), (p.line, p.column));
+    let p = index.position(3);
+    assert_eq!((2, 1), (p.line, p.column));
+}
+
+#[test]
+pub fn columns() {
+    let src = "x\n\t\u{e9}\u{1F600}z";
+    let index = LineIndex::new(src);
+    let p = index.position(src.find('z').unwrap());
+    assert_eq!(2, p.line);
+    assert_eq!(4, p.column);
+    assert_eq!(5, p.column_utf16);
+    assert_eq!(7, p.display_column);
+
+    let index = LineIndex::with_tab_width(src, 8);
+    assert_eq!(11, index.position(src.find('z').unwrap()).display_column);
+}
+
+#[test]
+pub fn offsets_round_trip() {
+    let src = "var a = '\u{1F600}';\n\tb\u{2029}c";
+    let index = LineIndex::new(src);
+    for (offset, _) in src.char_indices() {
+        let p = index.position(offset);
+        assert_eq!(Some(offset), index.offset(p.line, p.column));
+        assert_eq!(Some(offset), index.offset_utf16(p.line, p.column_utf16));
+    }
+    assert_eq!(None, index.offset(1, 100));
+    assert_eq!(None, index.offset_utf16(1, 11));
+    assert_eq!(None, index.offset(4, 1));
+}
+
+#[test]
+pub fn position_clamped() {
+    let src = "\u{e9}";
+    let index = LineIndex::new(src);
+    assert_eq!(0, index.position(1).offset);
+    assert_eq!(2, index.position(100).offset);
+}
+
+#[test]
+pub fn positioned_tokens() {
+    let src = "a;\r\n  /x/";
+    let index = LineIndex::new(src);
+    let tokens: Vec<_> = tokenize_str(src).with_positions(&index).collect();
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[4].token.kind);
+    assert_eq!((2, 3), (tokens[4].start.line, tokens[4].start.column));
+    assert_eq!((2, 6), (tokens[4].end.line, tokens[4].end.column));
+}
+
+#[test]
+pub fn positioned_tokens_long_line() {
+    // the code is on one long line, which must not be walked from its start for each token
+    let src = include_str!("jquery-1.12.4.min.js");
+    let index = LineIndex::new(src);
+    let mut last = index.position(0);
+    for token in tokenize_str(src).with_positions(&index) {
+        assert_eq!(last, token.start);
+        assert_eq!(token.token.span.end, token.end.offset);
+        last = token.end;
+    }
+    assert_eq!(index.position(src.len()), last);
+}
+
+#[test]
+pub fn position_from() {
+    let src = "ab\t\u{1F600}c\nd";
+    let index = LineIndex::new(src);
+    let from = index.position(1);
+    for offset in (0..src.len() + 2).filter(|&x| x > src.len() || src.is_char_boundary(x)) {
+        assert_eq!(index.position(offset), index.position_from(from, offset));
+    }
+}
+
+#[test]
+pub fn line_collector() {
+    let src = "a\r\nb\rc\nd\u{2028}e\u{2029}\r";
+    let index = LineIndex::new(src);
+    for offset in (0..=src.len()).filter(|&x| src.is_char_boundary(x)) {
+        // pushed in pieces, with the `\r\n` split
+        let mut lines = LineCollector::new();
+        lines.keep_line_at(offset);
+        lines.push_str(&src[..2]);
+        lines.push_str(&src[2..]);
+        let lines = lines.finish();
+        let line = index.position(offset).line;
+        let expected = SourceLine {
+            line,
+            offset: index.offset(line, 1).unwrap(),
+            text: index.line_text(line).unwrap().to_string(),
+        };
+        assert_eq!(vec![expected], lines, "offset {}", offset);
+    }
+}
+
+#[test]
+pub fn line_collector_keeps_only_wanted_lines() {
+    let mut lines = LineCollector::new();
+    lines.keep_line_at(4);
+    lines.keep_line_at(5);
+    lines.push_str("ab\ncd\n");
+    // already pushed whole
+    lines.keep_line_at(1);
+    lines.keep_line_at(8);
+    lines.push_str("ef\ngh");
+    let lines: Vec<_> = lines.finish().into_iter().map(|x| (x.line, x.offset, x.text)).collect();
+    assert_eq!(vec![(2, 3, "cd".to_string()), (3, 6, "ef".to_string())], lines);
+}
@@ -0,0 +1,94 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::number_value::*;
+
+fn number(raw: &str) -> f64 {
+    match number_value(raw) {
+        Some(NumberValue::Number(x)) => x,
+        x => panic!("{}: {:?}", raw, x),
+    }
+}
+
+fn bigint(raw: &str) -> String {
+    match number_value(raw) {
+        Some(NumberValue::BigInt(x)) => x.to_string(),
+        x => panic!("{}: {:?}", raw, x),
+    }
+}
+
+#[test]
+pub fn decimal() {
+    assert_eq!(0.0, number("0"));
+    assert_eq!(123.0, number("123"));
+    assert_eq!(0.5, number(".5"));
+    assert_eq!(5.0, number("5."));
+    assert_eq!(1_000_000.0, number("1_000_000"));
+    assert_eq!(1.5e-7, number("1.5e-7"));
+    assert_eq!(1e10, number("1E1_0"));
+    assert_eq!(0.1 + 0.2, number("0.30000000000000004"));
+    assert_eq!(f64::INFINITY, number("1e400"));
+    assert_eq!(5e-324, number("4.9406564584124654e-324"));
+    assert_eq!(89.0, number("089"));
+}
+
+#[test]
+pub fn radix() {
+    assert_eq!(255.0, number("0xFf"));
+    assert_eq!(255.0, number("0o377"));
+    assert_eq!(5.0, number("0b1_01"));
+    assert_eq!(15.0, number("017"));
+    assert_eq!(0.0, number("00"));
+}
+
+#[test]
+pub fn radix_rounding() {
+    // 2^53 + 1 is a tie and rounds to even, 2^53 + 3 rounds up
+    assert_eq!(9007199254740992.0, number("0x20000000000001"));
+    assert_eq!(9007199254740996.0, number("0x20000000000003"));
+    // ties above 64 bits, where the dropped bits are only seen through the sticky bit
+    assert_eq!(2f64.powi(89), number("0x20000000000001000000000"));
+    assert_eq!(2f64.powi(89) + 2f64.powi(37), number("0x20000000000001000000001"));
+    assert_eq!(2f64.powi(89) + 2f64.powi(38), number("0x20000000000003000000000"));
+    // the largest finite value, and half an ulp above it which rounds to infinity
+    assert_eq!(f64::MAX, number(&format!("0xfffffffffffff8{}", "0".repeat(242))));
+    assert_eq!(f64::MAX, number(&format!("0xfffffffffffffbff{}", "0".repeat(240))));
+    assert_eq!(f64::INFINITY, number(&format!("0xfffffffffffffc{}", "0".repeat(242))));
+    assert_eq!(f64::INFINITY, number(&format!("0x{}", "f".repeat(256))));
+}
+
+#[test]
+pub fn bigints() {
+    assert_eq!("0", bigint("0n"));
+    assert_eq!("18446744073709551616", bigint("0x1_0000_0000_0000_0000n"));
+    assert_eq!("123456789012345678901234567890", bigint("123456789012345678901234567890n"));
+    assert_eq!("5", bigint("0b101n"));
+    assert_eq!("8", bigint("0o10n"));
+}
+
+#[test]
+pub fn invalid() {
+    for raw in &["1e", "0x", "0b2", "1__0", "1_", "_1", "0_1", "01_0", "1.5n", "1e3n", "017n", "0x_1", "1_.5", "inf", "NaN", "1n_"] {
+        assert_eq!(None, number_value(raw), "{}", raw);
+    }
+}
+
+#[test]
+pub fn invalid_signs() {
+    for raw in &["+5", "-5", "+1n", "-1n", "0x+1", "0x-1", "0b+1", "0o+7", "0o-7", "1+2", "1.5-", "1e+-2", "0x1e+2"] {
+        assert_eq!(None, number_value(raw), "{}", raw);
+    }
+    assert_eq!(1.5e7, number("1.5e+7"));
+    assert_eq!(1e-2, number("1E-2"));
+}
+
+#[test]
+pub fn token_number_value() {
+    let tokens = tokenize("x = 0x10 + 2n");
+    assert_eq!(Some(NumberValue::Number(16.0)), tokens[4].number_value());
+    assert_eq!("2", match tokens[8].number_value() {
+        Some(NumberValue::BigInt(x)) => x.to_string(),
+        x => panic!("{:?}", x),
+    });
+    assert_eq!(None, tokens[0].number_value());
+}
@@ -0,0 +1,120 @@
+extern crate js_lex_rs;
+extern crate proptest;
+
+mod common;
+
+use js_lex_rs::*;
+use js_lex_rs::push::PushLexer;
+use common::js_fragment;
+use proptest::prelude::*;
+
+/// Lexes `src` split at the given byte offsets, moved back to character boundaries.
+fn lex_chunked(src: &str, splits: &[usize]) -> (Vec<Token>, PushLexer) {
+    let mut lexer = PushLexer::new();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for &split in splits {
+        let mut end = split.clamp(start, src.len());
+        while !src.is_char_boundary(end) {
+            end -= 1;
+        }
+        let end = end.max(start);
+        tokens.extend(lexer.feed(&src[start..end]));
+        start = end;
+    }
+    tokens.extend(lexer.feed(&src[start..]));
+    tokens.extend(lexer.finish());
+    (tokens, lexer)
+}
+
+fn check_chunked(src: &str, splits: &[usize]) {
+    let (tokens, lexer) = lex_chunked(src, splits);
+    let mut expected = tokenize_str_spanned(src);
+    assert_eq!(expected.by_ref().collect::<Vec<_>>(), tokens);
+    assert_eq!(expected.diagnostics(), lexer.diagnostics());
+}
+
+/// Whole tokens, so that chunk boundaries fall inside them.
+const TOKEN_PIECES: &[&str] = &[
+    "return", "if (a)", "function", "/re/g", "// comment", "/* c */", "'str'",
+    "\\u0061", "\\u{62}", "0x1_0n", "1e+5",
+];
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn independent_of_chunks(
+        fragments in proptest::collection::vec(js_fragment(TOKEN_PIECES), 0..24),
+        splits in proptest::collection::vec(0usize..120, 0..8),
+    ) {
+        let mut splits = splits;
+        splits.sort();
+        check_chunked(&fragments.concat(), &splits);
+    }
+}
+
+#[test]
+pub fn split_punctuator() {
+    let mut lexer = PushLexer::new();
+    assert!(lexer.feed("a >").iter().all(|t| t.kind != JsToken::Punctuation(">".to_string())));
+    assert!(lexer.feed(">").is_empty());
+    assert!(lexer.feed(">").is_empty());
+    let tokens = lexer.feed("= b");
+    assert_eq!(JsToken::Punctuation(">>>=".to_string()), tokens[0].kind);
+    assert_eq!((2, 6), (tokens[0].span.start, tokens[0].span.end));
+    assert_eq!(vec![JsToken::Word("b".to_string())], lexer.finish().into_iter().map(|t| t.kind).collect::<Vec<_>>());
+}
+
+#[test]
+pub fn split_string() {
+    let mut lexer = PushLexer::new();
+    assert!(lexer.feed("'abc").is_empty());
+    assert!(lexer.feed("\\").is_empty());
+    let tokens = lexer.feed("'def';");
+    assert_eq!(JsToken::StringLiteral("'abc\\'def'".to_string()), tokens[0].kind);
+    assert_eq!(JsToken::Punctuation(";".to_string()), tokens[1].kind);
+    assert!(lexer.finish().is_empty());
+    assert!(lexer.diagnostics().is_empty());
+}
+
+#[test]
+pub fn regexp_state_kept() {
+    let mut lexer = PushLexer::new();
+    let mut tokens = lexer.feed("x = a\n/");
+    tokens.extend(lexer.feed("2/g; if (x) /re"));
+    tokens.extend(lexer.feed("/.test(y)"));
+    tokens.extend(lexer.finish());
+    let kinds: Vec<JsToken> = tokens.into_iter().map(|t| t.kind).collect();
+    assert_eq!(tokenize("x = a\n/2/g; if (x) /re/.test(y)"), kinds);
+    assert!(kinds.contains(&JsToken::RegexpLiteral("re".to_string(), "".to_string())));
+}
+
+#[test]
+pub fn split_template_tail() {
+    // the tail pops the substitution off the state stack, which has to be undone while it is incomplete
+    let src = "f(`a${ {b} }cd`) / 2; `${x}` /re/";
+    for split in 0..src.len() {
+        check_chunked(src, &[split, split + 1]);
+    }
+}
+
+#[test]
+pub fn unterminated_at_finish() {
+    let mut lexer = PushLexer::new();
+    assert_eq!(2, lexer.feed("a /* b").len());
+    let tokens = lexer.finish();
+    assert_eq!(1, tokens.len());
+    assert_eq!(JsToken::MultilineComment("/* b".to_string()), tokens[0].kind);
+    assert_eq!((2, 6), (tokens[0].span.start, tokens[0].span.end));
+    assert_eq!(1, lexer.take_diagnostics().len());
+}
+
+#[test]
+pub fn jquery_chunked() {
+    let src = include_str!("jquery-1.12.4.js");
+    for &chunk in &[1, 7, 4096] {
+        let splits: Vec<usize> = (1..src.len() / chunk).map(|i| i * chunk).collect();
+        check_chunked(src, &splits);
+    }
+}
@@ -0,0 +1,118 @@
+extern crate js_lex_rs;
+extern crate proptest;
+
+use js_lex_rs::*;
+use js_lex_rs::reader::*;
+use proptest::prelude::*;
+use std::io::{self, Read};
+
+/// Returns at most `chunk` bytes per read, to split characters and tokens between reads.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+    interrupted: bool,
+}
+
+impl<'a> Read for ChunkedReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupted = !self.interrupted;
+        if self.interrupted {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+        }
+        let n = self.chunk.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+struct FailingReader;
+
+impl Read for FailingReader {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk on fire"))
+    }
+}
+
+fn chunked(data: &[u8], chunk: usize) -> ChunkedReader<'_> {
+    ChunkedReader { data, chunk, interrupted: false }
+}
+
+#[test]
+pub fn same_as_tokenize() {
+    let src = include_str!("jquery-1.12.4.js");
+    let tokens: Vec<JsToken> = tokenize_reader(src.as_bytes(), InvalidUtf8::Error)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(tokenize(src), tokens);
+}
+
+#[test]
+pub fn chunked_reads() {
+    let src = "let \u{e9} = '\u{1F600}\u{2028}' // \u{feff}\n";
+    for chunk in 1..5 {
+        let tokens: Vec<JsToken> = tokenize_reader(chunked(src.as_bytes(), chunk), InvalidUtf8::Error)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(tokenize(src), tokens);
+    }
+}
+
+#[test]
+pub fn replace_invalid() {
+    let src = b"a = '\xff\xe0\x80';";
+    let tokens: Vec<JsToken> = tokenize_reader(&src[..], InvalidUtf8::Replace)
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(JsToken::StringLiteral("'\u{fffd}\u{fffd}\u{fffd}'".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn error_on_invalid() {
+    let src = b"a = 1;\xc3(";
+    let mut tokens = tokenize_reader(&src[..], InvalidUtf8::Error);
+    assert_eq!(JsToken::Word("a".to_string()), tokens.next().unwrap().unwrap());
+    let results: Vec<io::Result<JsToken>> = tokens.collect();
+    assert_eq!(6, results.len());
+    let err = results[5].as_ref().unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    assert_eq!("stream did not contain valid UTF-8 at byte 6", err.to_string());
+}
+
+#[test]
+pub fn read_error() {
+    let mut tokens = tokenize_reader(FailingReader, InvalidUtf8::Replace);
+    assert_eq!("disk on fire", tokens.next().unwrap().unwrap_err().to_string());
+    assert!(tokens.next().is_none());
+}
+
+#[test]
+pub fn diagnostics() {
+    let mut tokens = tokenize_reader(&b"'abc"[..], InvalidUtf8::Error);
+    tokens.by_ref().for_each(drop);
+    assert_eq!(1, tokens.take_diagnostics().len());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn decodes_like_from_utf8_lossy(bytes in proptest::collection::vec(
+        prop_oneof![any::<u8>(), 0x80u8..0xc0, 0xe0u8..0xf5], 0..40), chunk in 1usize..6) {
+        let decoded: String = Utf8Chars::new(chunked(&bytes, chunk), InvalidUtf8::Replace).collect();
+        prop_assert_eq!(String::from_utf8_lossy(&bytes), decoded);
+
+        let mut chars = Utf8Chars::new(&bytes[..], InvalidUtf8::Error);
+        let decoded: String = chars.by_ref().collect();
+        match std::str::from_utf8(&bytes) {
+            Ok(s) => {
+                prop_assert_eq!(s, decoded);
+                prop_assert!(chars.take_error().is_none());
+            },
+            Err(e) => {
+                prop_assert_eq!(e.valid_up_to(), decoded.len());
+                prop_assert!(chars.take_error().is_some());
+            },
+        }
+    }
+}
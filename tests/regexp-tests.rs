@@ -0,0 +1,246 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::regexp::*;
+
+fn terms(pattern: &Pattern) -> &[Node] {
+    match pattern.body.kind {
+        NodeKind::Disjunction(ref alternatives) => match alternatives[0].kind {
+            NodeKind::Alternative(ref terms) => terms,
+            ref x => panic!("{:?}", x),
+        },
+        ref x => panic!("{:?}", x),
+    }
+}
+
+fn error(pattern: &str, flags: &str) -> &'static str {
+    match parse_regexp(pattern, flags) {
+        Err(e) => e.message,
+        Ok(x) => panic!("/{}/{} parsed: {:?}", pattern, flags, x),
+    }
+}
+
+#[test]
+pub fn alternatives_and_quantifiers() {
+    let pattern = parse_regexp("ab+?|c{2,3}", "g").unwrap();
+    assert!(pattern.flags.global);
+    let alternatives = match pattern.body.kind {
+        NodeKind::Disjunction(ref x) => x,
+        ref x => panic!("{:?}", x),
+    };
+    assert_eq!(2, alternatives.len());
+    let terms = terms(&pattern);
+    assert_eq!(NodeKind::Char('a' as u32), terms[0].kind);
+    match terms[1].kind {
+        NodeKind::Quantifier { min: 1, max: None, greedy: false, ref body } => {
+            assert_eq!(NodeKind::Char('b' as u32), body.kind);
+        },
+        ref x => panic!("{:?}", x),
+    }
+    assert_eq!((1, 4), (terms[1].start, terms[1].end));
+    match alternatives[1].kind {
+        NodeKind::Alternative(ref terms) => match terms[0].kind {
+            NodeKind::Quantifier { min: 2, max: Some(3), greedy: true, .. } => {},
+            ref x => panic!("{:?}", x),
+        },
+        ref x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+pub fn groups_and_backreferences() {
+    let pattern = parse_regexp(r"\2(a)(?<year>\d{4})(?:x)(?<=y)(?!z)\k<year>", "").unwrap();
+    assert_eq!(2, pattern.group_count);
+    assert_eq!(vec!["year".to_string()], pattern.group_names);
+    let terms = terms(&pattern);
+    assert_eq!(NodeKind::Backreference(Backreference::Index(2)), terms[0].kind);
+    match terms[2].kind {
+        NodeKind::Group { index: 2, name: Some(ref name), .. } => assert_eq!("year", name),
+        ref x => panic!("{:?}", x),
+    }
+    match terms[4].kind {
+        NodeKind::Lookaround { behind: true, negated: false, .. } => {},
+        ref x => panic!("{:?}", x),
+    }
+    match terms[5].kind {
+        NodeKind::Lookaround { behind: false, negated: true, .. } => {},
+        ref x => panic!("{:?}", x),
+    }
+    assert_eq!(NodeKind::Backreference(Backreference::Named("year".to_string())), terms[6].kind);
+}
+
+#[test]
+pub fn classes() {
+    let pattern = parse_regexp(r"[^a-z\d\-]", "").unwrap();
+    let expected = CharacterClass {
+        negated: true,
+        op: ClassSetOp::Union,
+        items: vec![
+            ClassItem::Range('a' as u32, 'z' as u32),
+            ClassItem::Escape(ClassEscape::Digit),
+            ClassItem::Char('-' as u32),
+        ],
+    };
+    assert_eq!(NodeKind::Class(expected), terms(&pattern)[0].kind);
+}
+
+#[test]
+pub fn class_ranges_split_surrogates() {
+    let pattern = parse_regexp("[a-\u{1F600}]", "").unwrap();
+    let expected = vec![ClassItem::Range('a' as u32, 0xd83d), ClassItem::Char(0xde00)];
+    match terms(&pattern)[0].kind {
+        NodeKind::Class(ref class) => assert_eq!(expected, class.items),
+        ref x => panic!("{:?}", x),
+    }
+    let pattern = parse_regexp("[\u{1F600}-\u{FFFF}]", "").unwrap();
+    let expected = vec![ClassItem::Char(0xd83d), ClassItem::Range(0xde00, 0xffff)];
+    match terms(&pattern)[0].kind {
+        NodeKind::Class(ref class) => assert_eq!(expected, class.items),
+        ref x => panic!("{:?}", x),
+    }
+    // U+DE00 to U+D83D
+    assert_eq!("range out of order in character class", error("[\u{1F600}-\u{1F602}]", ""));
+    assert!(parse_regexp("[\u{1F600}-\u{1F602}]", "u").is_ok());
+}
+
+#[test]
+pub fn escapes() {
+    let pattern = parse_regexp(r"\x41\u{1F600}😀\cJ\0\/", "u").unwrap();
+    let chars: Vec<_> = terms(&pattern).iter().map(|t| t.kind.clone()).collect();
+    assert_eq!(vec![
+        NodeKind::Char(0x41),
+        NodeKind::Char(0x1F600),
+        NodeKind::Char(0x1F600),
+        NodeKind::Char(10),
+        NodeKind::Char(0),
+        NodeKind::Char('/' as u32),
+    ], chars);
+}
+
+#[test]
+pub fn unicode_mode_splits_surrogates() {
+    assert_eq!(1, terms(&parse_regexp("\u{1F600}", "u").unwrap()).len());
+    let pattern = parse_regexp("\u{1F600}+", "").unwrap();
+    let terms = terms(&pattern);
+    assert_eq!(NodeKind::Char(0xd83d), terms[0].kind);
+    match terms[1].kind {
+        NodeKind::Quantifier { ref body, .. } => assert_eq!(NodeKind::Char(0xde00), body.kind),
+        ref x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+pub fn property_escapes() {
+    let pattern = parse_regexp(r"\p{Script=Greek}\P{Lu}\p{ASCII_Hex_Digit}", "u").unwrap();
+    assert_eq!(NodeKind::ClassEscape(ClassEscape::Property {
+        name: "Script".to_string(),
+        value: Some("Greek".to_string()),
+        negated: false,
+    }), terms(&pattern)[0].kind);
+    assert_eq!("invalid property name", error(r"\p{Foo}", "u"));
+    assert_eq!("invalid property name", error(r"\p{gc=Foo}", "u"));
+    assert_eq!("invalid property name", error(r"\p{RGI_Emoji}", "u"));
+    assert!(parse_regexp(r"\p{RGI_Emoji}", "v").is_ok());
+    assert_eq!("invalid property name", error(r"\P{RGI_Emoji}", "v"));
+    // without `u`, `\p` is just `p`
+    assert_eq!(NodeKind::Char('p' as u32), terms(&parse_regexp(r"\p{Foo}", "").unwrap())[0].kind);
+}
+
+#[test]
+pub fn class_sets() {
+    let pattern = parse_regexp(r"[[a-z]--[aeiou]][\p{L}&&\p{ASCII}][\q{abc|d}x]", "v").unwrap();
+    let terms = terms(&pattern);
+    match terms[0].kind {
+        NodeKind::Class(ref class) => {
+            assert_eq!(ClassSetOp::Subtraction, class.op);
+            assert_eq!(2, class.items.len());
+        },
+        ref x => panic!("{:?}", x),
+    }
+    match terms[1].kind {
+        NodeKind::Class(ref class) => assert_eq!(ClassSetOp::Intersection, class.op),
+        ref x => panic!("{:?}", x),
+    }
+    match terms[2].kind {
+        NodeKind::Class(ref class) => {
+            assert_eq!(ClassItem::Strings(vec![vec![0x61, 0x62, 0x63], vec![0x64]]), class.items[0]);
+            assert!(class.may_contain_strings());
+        },
+        ref x => panic!("{:?}", x),
+    }
+
+    assert_eq!("invalid set operation in character class", error("[a&&b--c]", "v"));
+    assert_eq!("invalid set operation in character class", error("[ab&&c]", "v"));
+    assert_eq!("invalid set operation in character class", error("[a&&&b]", "v"));
+    assert_eq!("invalid character in character class", error("[a-]", "v"));
+    assert_eq!("invalid character in character class", error("[a||b]", "v"));
+    assert_eq!("negated character class may contain strings", error(r"[^\q{ab}]", "v"));
+    assert!(parse_regexp(r"[^\q{a|b}]", "v").is_ok());
+}
+
+#[test]
+pub fn annex_b() {
+    // valid without `u`, errors with it
+    for pattern in &[r"]", r"{", r"a{1", r"\c", r"\8", r"\1", r"\00", r"\q", r"(?=a)*", r"[\d-z]", r"\u{6"] {
+        assert!(parse_regexp(pattern, "").is_ok(), "{}", pattern);
+        assert!(parse_regexp(pattern, "u").is_err(), "{}", pattern);
+    }
+    let pattern = parse_regexp(r"\12\8\c", "").unwrap();
+    let chars: Vec<_> = terms(&pattern).iter().map(|t| t.kind.clone()).collect();
+    assert_eq!(vec![
+        NodeKind::Char(0o12),
+        NodeKind::Char('8' as u32),
+        NodeKind::Char('\\' as u32),
+        NodeKind::Char('c' as u32),
+    ], chars);
+    // `\k` is an identity escape unless there are named groups
+    assert!(parse_regexp(r"\k", "").is_ok());
+    assert_eq!("invalid named reference", error(r"\k(?<a>)", ""));
+}
+
+#[test]
+pub fn early_errors() {
+    assert_eq!("nothing to repeat", error("*", ""));
+    assert_eq!("nothing to repeat", error("a**", ""));
+    assert_eq!("nothing to repeat", error("^*", ""));
+    assert_eq!("nothing to repeat", error("{1}", ""));
+    assert_eq!("numbers out of order in {} quantifier", error("a{2,1}", ""));
+    assert_eq!("invalid quantifier on a lookaround", error("(?<=a)+", ""));
+    assert_eq!("range out of order in character class", error("[z-a]", ""));
+    assert_eq!("unterminated character class", error("[a", ""));
+    assert_eq!("unterminated group", error("(a", ""));
+    assert_eq!("unmatched ')'", error("a)", ""));
+    assert_eq!("invalid group", error("(?a)", ""));
+    assert_eq!("duplicate capture group name", error("(?<a>)(?<a>)", ""));
+    assert_eq!("invalid capture group name", error("(?<1>)", ""));
+    assert_eq!("reference to an undefined group name", error(r"\k<b>(?<a>)", ""));
+    assert_eq!("invalid escape", error(r"\-", "u"));
+    assert_eq!("invalid character class range", error(r"[\d-z]", "u"));
+    assert_eq!("\\ at end of pattern", error("\\", ""));
+    assert_eq!("invalid regular expression flags", error("a", "gg"));
+
+    let e = parse_regexp("ab[z-a]", "").unwrap_err();
+    assert_eq!(3, e.offset);
+}
+
+#[test]
+pub fn regexp_tokens() {
+    let tokens = tokenize("x = /a(b)\\1/gu");
+    let pattern = tokens[4].parse_regexp().unwrap().unwrap();
+    assert_eq!(1, pattern.group_count);
+    assert!(pattern.flags.unicode);
+    assert_eq!(None, tokens[0].parse_regexp());
+}
+
+#[test]
+pub fn regexps_in_jquery() {
+    let src = include_str!("jquery-1.12.4.js");
+    let mut count = 0;
+    for token in tokenize_str_ref(src) {
+        if let Some(result) = token.parse_regexp() {
+            assert!(result.is_ok(), "{:?}: {:?}", token, result);
+            count += 1;
+        }
+    }
+    assert!(count > 50, "{}", count);
+}
@@ -0,0 +1,94 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::line_index::LineCollector;
+use js_lex_rs::render::*;
+
+fn render(src: &str) -> String {
+    let (_, diagnostics) = tokenize_with_diagnostics(src);
+    let renderer = Renderer::new("app.js", src);
+    diagnostics.iter().map(|d| renderer.render(d)).collect()
+}
+
+#[test]
+pub fn code_frame() {
+    let expected = "\
+app.js:2:5: error[unterminated-string-literal]: unterminated string literal
+  |
+2 | b = 'abc
+  |     ^^^^
+";
+    assert_eq!(expected, render("a;\nb = 'abc\nc;"));
+}
+
+#[test]
+pub fn tabs_are_kept() {
+    let expected = "\
+app.js:1:3: error[unexpected-character]: unexpected character '@'
+  |
+1 | \tx@
+  | \t ^
+";
+    assert_eq!(expected, render("\tx@"));
+}
+
+#[test]
+pub fn span_past_line_end() {
+    let expected = "\
+app.js:1:3: error[unterminated-comment]: unterminated comment
+  |
+1 | a /* b
+  |   ^^^^
+";
+    assert_eq!(expected, render("a /* b\nc"));
+}
+
+#[test]
+pub fn wide_line_number() {
+    let src = format!("{}0x", "\n".repeat(11));
+    let expected = "\
+app.js:12:1: error[invalid-numeric-literal]: invalid numeric literal
+   |
+12 | 0x
+   | ^^
+";
+    assert_eq!(expected, render(&src));
+}
+
+#[test]
+pub fn color() {
+    let src = "'a";
+    let (_, diagnostics) = tokenize_with_diagnostics(src);
+    let plain = Renderer::new("app.js", src).render(&diagnostics[0]);
+    let colored = Renderer::new("app.js", src).with_color(true).render(&diagnostics[0]);
+    assert!(!plain.contains('\x1b'));
+    assert!(colored.contains("\x1b[1;31merror[unterminated-string-literal]\x1b[0m"));
+    assert!(colored.contains("\x1b[1;31m^^\x1b[0m"));
+}
+
+#[test]
+pub fn without_source() {
+    let (_, diagnostics) = tokenize_with_diagnostics("a = 'abc");
+    let expected = "app.js: byte 4: error[unterminated-string-literal]: unterminated string literal\n";
+    assert_eq!(expected, Renderer::without_source("app.js").render(&diagnostics[0]));
+}
+
+#[test]
+pub fn with_lines() {
+    let src = "a;\r\nb = 'abc\r\n`x\ny";
+    let (tokens, diagnostics) = tokenize_with_diagnostics(src);
+    let mut lines = LineCollector::new();
+    for diagnostic in &diagnostics {
+        lines.keep_line_at(diagnostic.span.start);
+    }
+    for token in &tokens {
+        lines.push_str(&token.kind.source_text());
+    }
+    let lines = lines.finish();
+    assert_eq!(2, lines.len());
+    let full = Renderer::new("app.js", src);
+    let partial = Renderer::with_lines("app.js", &lines);
+    for diagnostic in &diagnostics {
+        assert_eq!(full.render(diagnostic), partial.render(diagnostic));
+    }
+}
@@ -0,0 +1,50 @@
+extern crate js_lex_rs;
+extern crate proptest;
+
+mod common;
+
+use js_lex_rs::*;
+use common::js_fragment;
+use proptest::prelude::*;
+
+fn join_tokens(src: &str) -> String {
+    tokenize(src).iter().map(|t| t.source_text()).collect()
+}
+
+fn join_token_refs(src: &str) -> String {
+    tokenize_str_ref(src).map(|t| t.source_text()).collect()
+}
+
+fn check_spans(src: &str) {
+    for token in tokenize_str_spanned(src) {
+        assert_eq!(&src[token.span.start..token.span.end], token.kind.source_text());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn round_trip(fragments in proptest::collection::vec(js_fragment(&[]), 0..48)) {
+        let src = fragments.concat();
+        prop_assert_eq!(&src, &join_tokens(&src));
+        prop_assert_eq!(&src, &join_token_refs(&src));
+        check_spans(&src);
+    }
+}
+
+#[test]
+pub fn round_trip_malformed() {
+    for src in &["//", "/*", "/**", "/*/", "'abc", "`a${", "/a", "/[/", "#", "#!", "\\u00", "0x", "1e", "\r", "a\r\n/x/g"] {
+        assert_eq!(*src, join_tokens(src));
+        check_spans(src);
+    }
+}
+
+#[test]
+pub fn round_trip_jquery() {
+    for src in &[include_str!("jquery-1.12.4.js"), include_str!("jquery-1.12.4.min.js")] {
+        assert!(*src == join_tokens(src));
+        assert!(*src == join_token_refs(src));
+    }
+}
@@ -0,0 +1,64 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::string_value::*;
+
+fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+#[test]
+pub fn escapes() {
+    let value = string_value(r"'a\x41B\u{1F600}\n\t\'\q'").unwrap();
+    assert_eq!(utf16("aAB\u{1F600}\n\t'q"), value.utf16);
+    assert_eq!("aAB\u{1F600}\n\t'q", value.to_string_lossy());
+    assert!(!value.legacy_octal);
+    assert_eq!(utf16("\u{8}\u{c}\u{b}\r\"\\"), string_value(r#""\b\f\v\r\"\\""#).unwrap().utf16);
+}
+
+#[test]
+pub fn line_continuation() {
+    assert_eq!(utf16("ab"), string_value("'a\\\nb'").unwrap().utf16);
+    assert_eq!(utf16("ab"), string_value("'a\\\r\nb'").unwrap().utf16);
+    assert_eq!(utf16("ab"), string_value("'a\\\u{2028}b'").unwrap().utf16);
+    assert_eq!(utf16("a\u{2029}b"), string_value("'a\u{2029}b'").unwrap().utf16);
+    assert_eq!(None, string_value("'a\nb'"));
+}
+
+#[test]
+pub fn octal() {
+    let value = string_value(r"'\0'").unwrap();
+    assert_eq!(vec![0], value.utf16);
+    assert!(!value.legacy_octal);
+
+    let value = string_value(r"'\08\101\400\777\8'").unwrap();
+    assert_eq!(vec![0, '8' as u16, 0o101, 0o40, '0' as u16, 0o77, '7' as u16, '8' as u16], value.utf16);
+    assert!(value.legacy_octal);
+}
+
+#[test]
+pub fn lone_surrogates() {
+    let value = string_value(r"'\uD800x\u{DC00}😀'").unwrap();
+    assert_eq!(vec![0xd800, 'x' as u16, 0xdc00, 0xd83d, 0xde00], value.utf16);
+    assert_eq!("\u{fffd}x\u{fffd}\u{1F600}", value.to_string_lossy());
+
+    // escaped surrogate pairs still form one character
+    assert_eq!("\u{1F600}", string_value(r"'\uD83D\uDE00'").unwrap().to_string_lossy());
+}
+
+#[test]
+pub fn malformed() {
+    assert_eq!(None, string_value(r"'\x4'"));
+    assert_eq!(None, string_value(r"'\u12'"));
+    assert_eq!(None, string_value(r"'\u{}'"));
+    assert_eq!(None, string_value(r"'\u{110000}'"));
+    assert_eq!(None, string_value("'abc"));
+    assert_eq!(None, string_value("'a\\"));
+}
+
+#[test]
+pub fn token_string_value() {
+    let tokens = tokenize("x = \"caf\\u00e9\"");
+    assert_eq!("caf\u{e9}", tokens[4].string_value().unwrap().to_string_lossy());
+    assert_eq!(None, tokens[0].string_value());
+}
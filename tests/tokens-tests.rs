@@ -1,6 +1,7 @@
 extern crate js_lex_rs;
 
 use js_lex_rs::*;
+use std::borrow::Cow;
 
 #[test]
 pub fn whitespace() {
@@ -16,18 +17,46 @@ pub fn line_terminator() {
     assert_eq!(JsToken::LineTerminator("\r\n".to_string()), tokens[0]);
 }
 
+#[test]
+pub fn whitespace_unicode() {
+    let src = "\u{feff}\u{1680}\u{2000}\u{200a}\u{202f}\u{205f}\u{3000}\u{a0}\u{b}\u{c}";
+    let tokens = tokenize(src);
+    assert_eq!(1, tokens.len());
+    assert_eq!(JsToken::Whitespace(src.to_string()), tokens[0]);
+}
+
+#[test]
+pub fn line_terminators_split() {
+    let tokens = tokenize("\n\n\r\r\n\u{2028}\u{2029}");
+    assert_eq!(6, tokens.len());
+    assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[1]);
+    assert_eq!(JsToken::LineTerminator("\r".to_string()), tokens[2]);
+    assert_eq!(JsToken::LineTerminator("\r\n".to_string()), tokens[3]);
+    assert_eq!(JsToken::LineTerminator("\u{2028}".to_string()), tokens[4]);
+    assert_eq!(JsToken::LineTerminator("\u{2029}".to_string()), tokens[5]);
+}
+
+#[test]
+pub fn line_comment_paragraph_separator() {
+    let tokens = tokenize("//qwe\u{2029}x");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::LineComment("//qwe".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineTerminator("\u{2029}".to_string()), tokens[1]);
+}
+
 #[test]
 pub fn line_comment() {
     let tokens = tokenize("//qwe");
     assert_eq!(1, tokens.len());
-    assert_eq!(JsToken::LineComment("qwe".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineComment("//qwe".to_string()), tokens[0]);
 }
 
 #[test]
 pub fn line_comment_newline() {
     let tokens = tokenize("//qwe\n");
     assert_eq!(2, tokens.len());
-    assert_eq!(JsToken::LineComment("qwe".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineComment("//qwe".to_string()), tokens[0]);
     assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[1]);
 }
 
@@ -35,7 +64,30 @@ pub fn line_comment_newline() {
 pub fn multiline_comment() {
     let tokens = tokenize("/* qwe* */");
     assert_eq!(1, tokens.len());
-    assert_eq!(JsToken::MultilineComment(" qwe* ".to_string()), tokens[0]);
+    assert_eq!(JsToken::MultilineComment("/* qwe* */".to_string()), tokens[0]);
+}
+
+#[test]
+pub fn hashbang() {
+    let tokens = tokenize("#!/usr/bin/env node\n/q/");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::Hashbang("#!/usr/bin/env node".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[1]);
+    assert_eq!(JsToken::RegexpLiteral("q".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn hashbang_not_at_start() {
+    let tokens = tokenize(" #!x");
+    assert_eq!(JsToken::Unknown("#".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn private_name() {
+    let tokens = tokenize_no_whitespace("class A { #count = 0; m() { return this.#count / 2; } }");
+    assert_eq!(JsToken::PrivateName("#count".to_string()), tokens[3]);
+    assert_eq!(JsToken::PrivateName("#count".to_string()), tokens[14]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[15]);
 }
 
 #[test]
@@ -49,6 +101,39 @@ pub fn word() {
     assert_eq!(JsToken::Word("false".to_string()), tokens[4]);
 }
 
+#[test]
+pub fn word_unicode_escapes() {
+    let tokens = tokenize("a\\u0062c \\u{1F600}x");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::Word("a\\u0062c".to_string()), tokens[0]);
+    assert_eq!(Some("abc".into()), tokens[0].identifier_name());
+    assert_eq!(JsToken::Word("\\u{1F600}x".to_string()), tokens[2]);
+    assert_eq!(Some("\u{1F600}x".into()), tokens[2].identifier_name());
+}
+
+#[test]
+pub fn word_malformed_escape() {
+    let tokens = tokenize("a\\u00");
+    assert_eq!(JsToken::Word("a".to_string()), tokens[0]);
+    assert_eq!(JsToken::Unknown("\\".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn word_id_continue() {
+    let tokens = tokenize("a\u{200c}b\u{200d} e\u{0301} \u{2118}");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::Word("a\u{200c}b\u{200d}".to_string()), tokens[0]);
+    assert_eq!(JsToken::Word("e\u{0301}".to_string()), tokens[2]);
+    assert_eq!(JsToken::Word("\u{2118}".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn word_escaped_keyword() {
+    let tokens = tokenize_no_whitespace("\\u0069f (1)/qwe/");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("qwe".to_string(), "".to_string()), tokens[4]);
+}
+
 #[test]
 pub fn num_binary() {
     let tokens = tokenize("0b001");
@@ -84,6 +169,79 @@ pub fn num_hex() {
     assert_eq!(JsToken::NumberLiteral("0x0ABCf".to_string()), tokens[0]);
 }
 
+#[test]
+pub fn num_bigint() {
+    let tokens = tokenize_no_whitespace("123n 0n 0x1Fn 0b1n 0o7n");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("123n".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("0n".to_string()), tokens[1]);
+    assert_eq!(JsToken::NumberLiteral("0x1Fn".to_string()), tokens[2]);
+    assert_eq!(JsToken::NumberLiteral("0b1n".to_string()), tokens[3]);
+    assert_eq!(JsToken::NumberLiteral("0o7n".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn num_separators() {
+    let tokens = tokenize_no_whitespace("1_000_000 0x_1 0xFF_FF 1.0_1e1_0");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("1_000_000".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("0x".to_string()), tokens[1]);
+    assert_eq!(JsToken::Word("_1".to_string()), tokens[2]);
+    assert_eq!(JsToken::NumberLiteral("0xFF_FF".to_string()), tokens[3]);
+    assert_eq!(JsToken::NumberLiteral("1.0_1e1_0".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn num_leading_dot() {
+    let tokens = tokenize_no_whitespace(".5 .5e3 a.b");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::NumberLiteral(".5".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral(".5e3".to_string()), tokens[1]);
+    assert_eq!(JsToken::Punctuation(".".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn num_signed_exponent() {
+    let tokens = tokenize_no_whitespace("1e+10 1E-10");
+    assert_eq!(2, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("1e+10".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("1E-10".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn num_zero_fraction_exponent() {
+    let tokens = tokenize_no_whitespace("0.5 0e3 0.e1 0");
+    assert_eq!(4, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("0.5".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("0e3".to_string()), tokens[1]);
+    assert_eq!(JsToken::NumberLiteral("0.e1".to_string()), tokens[2]);
+    assert_eq!(JsToken::NumberLiteral("0".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn num_legacy_octal() {
+    let tokens = tokenize_no_whitespace("017.5");
+    assert_eq!(2, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("017".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral(".5".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn num_non_octal_decimal() {
+    let tokens = tokenize_no_whitespace("089 08.5e1");
+    assert_eq!(2, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("089".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("08.5e1".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn num_member_access() {
+    let tokens = tokenize_no_whitespace("1..toString()");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::NumberLiteral("1.".to_string()), tokens[0]);
+    assert_eq!(JsToken::Punctuation(".".to_string()), tokens[1]);
+}
+
 #[test]
 pub fn string_single_plain() {
     let tokens = tokenize("'qwe' ");
@@ -123,13 +281,11 @@ fn tokenize_no_whitespace(s: &str) -> Vec<JsToken> {
     let tokens: Vec<_> = tokenize(s)
         .into_iter()
         .filter(|x|
-            match *x {
+            !matches!(*x,
                 JsToken::Whitespace(_) |
                 JsToken::LineTerminator(_) |
                 JsToken::MultilineComment(_) |
-                JsToken::LineComment(_) => false,
-                _ => true,
-            }
+                JsToken::LineComment(_))
         )
         .collect();
 
@@ -241,9 +397,10 @@ pub fn test_div() {
 
 #[test]
 pub fn test_div_eq() {
-    let tokens = tokenize_no_whitespace("/=");
-    assert_eq!(1, tokens.len());
-    assert_eq!(JsToken::Punctuation("/=".to_string()), tokens[0]);
+    // at the start of an expression, `/=` begins a regexp
+    let tokens = tokenize_no_whitespace("x/=");
+    assert_eq!(2, tokens.len());
+    assert_eq!(JsToken::Punctuation("/=".to_string()), tokens[1]);
 }
 
 #[test]
@@ -282,6 +439,52 @@ pub fn test_modulo() {
     assert_eq!(JsToken::Punctuation("%=".to_string()), tokens[1]);
 }
 
+#[test]
+pub fn test_modern_punctuators() {
+    let tokens = tokenize_no_whitespace("=> ... ?. ?? ??= ** **= >>> >>>= &&= ||=");
+    let expected = ["=>", "...", "?.", "??", "??=", "**", "**=", ">>>", ">>>=", "&&=", "||="];
+    assert_eq!(expected.len(), tokens.len());
+    for (e, t) in expected.iter().zip(tokens.iter()) {
+        assert_eq!(&JsToken::Punctuation(e.to_string()), t);
+    }
+}
+
+#[test]
+pub fn test_optional_chaining_digit() {
+    let tokens = tokenize_no_whitespace("a?.5:b");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::Punctuation("?".to_string()), tokens[1]);
+    assert_eq!(JsToken::NumberLiteral(".5".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_arrow_expr_regexp() {
+    let tokens = tokenize_no_whitespace("x => /q/");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("q".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_arrow_body_regexp() {
+    let tokens = tokenize_no_whitespace("x => {}\n/q/");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("q".to_string(), "".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn test_spread_regexp() {
+    let tokens = tokenize_no_whitespace("[.../q/]");
+    assert_eq!(4, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("q".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_optional_chaining_div() {
+    let tokens = tokenize_no_whitespace("a?.b/2");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+}
+
 #[test]
 pub fn test_regexp() {
     let tokens = tokenize_no_whitespace("/qwe/gi");
@@ -289,6 +492,102 @@ pub fn test_regexp() {
     assert_eq!(JsToken::RegexpLiteral("qwe".to_string(), "gi".to_string()), tokens[0]);
 }
 
+#[test]
+pub fn test_regexp_starting_with_eq() {
+    let tokens = tokenize_no_whitespace("s.split(/=/)");
+    assert_eq!(6, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("=".to_string(), "".to_string()), tokens[4]);
+    let tokens = tokenize_no_whitespace("x = /=+/g");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("=+".to_string(), "g".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_div_eq_assignment() {
+    let tokens = tokenize_no_whitespace("x /= 2; a[0] /=b");
+    assert_eq!(JsToken::Punctuation("/=".to_string()), tokens[1]);
+    assert_eq!(JsToken::Punctuation("/=".to_string()), tokens[8]);
+}
+
+#[test]
+pub fn test_regexp_class() {
+    let tokens = tokenize_no_whitespace("/[/]/g; /[^/]+$/; /[\\]/]/");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("[/]".to_string(), "g".to_string()), tokens[0]);
+    assert_eq!(JsToken::RegexpLiteral("[^/]+$".to_string(), "".to_string()), tokens[2]);
+    assert_eq!(JsToken::RegexpLiteral("[\\]/]".to_string(), "".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn test_regexp_unterminated() {
+    let tokens = tokenize("/abc\nx");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::Unknown("/abc".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[1]);
+    assert_eq!(JsToken::Word("x".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_regexp_unterminated_eof() {
+    let tokens = tokenize("/[/");
+    assert_eq!(1, tokens.len());
+    assert_eq!(JsToken::Unknown("/[/".to_string()), tokens[0]);
+}
+
+#[test]
+pub fn test_regexp_unterminated_resync() {
+    let tokens = tokenize_no_whitespace("x = /ab\n/cd/g");
+    assert_eq!(4, tokens.len());
+    assert_eq!(JsToken::Unknown("/ab".to_string()), tokens[2]);
+    assert_eq!(JsToken::RegexpLiteral("cd".to_string(), "g".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn string_unterminated() {
+    let tokens = tokenize("'abc\nx = \"d");
+    assert_eq!(7, tokens.len());
+    assert_eq!(JsToken::StringLiteral("'abc".to_string()), tokens[0]);
+    assert_eq!(JsToken::LineTerminator("\n".to_string()), tokens[1]);
+    assert_eq!(JsToken::Word("x".to_string()), tokens[2]);
+    assert_eq!(JsToken::StringLiteral("\"d".to_string()), tokens[6]);
+}
+
+#[test]
+pub fn string_unterminated_resync() {
+    let tokens = tokenize_no_whitespace("a 'b\r\n/re/");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::StringLiteral("'b".to_string()), tokens[1]);
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn string_line_separator() {
+    let tokens = tokenize("'a\u{2028}b'");
+    assert_eq!(1, tokens.len());
+    assert_eq!(JsToken::StringLiteral("'a\u{2028}b'".to_string()), tokens[0]);
+}
+
+#[test]
+pub fn unknown_char_resync() {
+    let tokens = tokenize_no_whitespace("x @@ /re/");
+    assert_eq!(4, tokens.len());
+    assert_eq!(JsToken::Unknown("@".to_string()), tokens[1]);
+    assert_eq!(JsToken::Unknown("@".to_string()), tokens[2]);
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn test_regexp_flags() {
+    let tokens = tokenize_no_whitespace("/a/dgimsuyZ\u{e9}");
+    assert_eq!(1, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("a".to_string(), "dgimsuyZ\u{e9}".to_string()), tokens[0]);
+    assert!(is_valid_regexp_flags("dgimsy"));
+    assert!(is_valid_regexp_flags("v"));
+    assert!(!is_valid_regexp_flags("gg"));
+    assert!(!is_valid_regexp_flags("uv"));
+    assert!(!is_valid_regexp_flags("Z"));
+}
+
 #[test]
 pub fn test_regexp_div_disambig_1() {
     let tokens = tokenize_no_whitespace("/qwe/");
@@ -363,6 +662,15 @@ pub fn test_string_escape_unicode() {
     assert_eq!(JsToken::StringLiteral("'\\u{1234ABCD}'".to_string()), tokens[0]);
 }
 
+#[test]
+pub fn test_string_div() {
+    let tokens = tokenize_no_whitespace("x = 'a' / 2 / \"b\"");
+    assert_eq!(7, tokens.len());
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[5]);
+    assert!(tokenize_with_diagnostics("x = 'a' / 2").1.is_empty());
+}
+
 #[test]
 pub fn test_string_increment_prefix() {
     let tokens = tokenize_no_whitespace("1\n++{}/q");
@@ -412,6 +720,20 @@ pub fn test_string_paren_return_nl_expr_regexp() {
     assert_eq!(JsToken::RegexpLiteral("qwe".to_string(), "".to_string()), tokens[3]);
 }
 
+#[test]
+pub fn test_string_paren_return_ls_expr_regexp() {
+    let tokens = tokenize_no_whitespace("return\u{2028}{}/qwe/");
+    assert_eq!(4, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("qwe".to_string(), "".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn test_string_increment_prefix_ps() {
+    let tokens = tokenize_no_whitespace("1/*\u{2029}*/++{}/q");
+    assert_eq!(6, tokens.len());
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[4]);
+}
+
 #[test]
 pub fn test_string_paren_return_id_div() {
     let tokens = tokenize_no_whitespace("returnqq /qwe/");
@@ -433,6 +755,223 @@ pub fn test_string_paren_regexp_funcall() {
     assert_eq!(JsToken::Punctuation("/".to_string()), tokens[4]);
 }
 
+#[test]
+pub fn test_regexp_after_typeof() {
+    let tokens = tokenize_no_whitespace("typeof /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_instanceof() {
+    let tokens = tokenize_no_whitespace("x instanceof /re/.constructor");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_regexp_after_in() {
+    let tokens = tokenize_no_whitespace("'a' in /re/");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_regexp_after_new() {
+    let tokens = tokenize_no_whitespace("new /re/.constructor");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_delete() {
+    let tokens = tokenize_no_whitespace("delete /re/.x");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_void() {
+    let tokens = tokenize_no_whitespace("void /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_case() {
+    let tokens = tokenize_no_whitespace("case /a/:");
+    assert_eq!(JsToken::RegexpLiteral("a".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_do() {
+    let tokens = tokenize_no_whitespace("do /re/.test(s); while(0)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_else() {
+    let tokens = tokenize_no_whitespace("if (x) y; else /re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[7]);
+}
+
+#[test]
+pub fn test_regexp_after_await() {
+    let tokens = tokenize_no_whitespace("await /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_yield() {
+    let tokens = tokenize_no_whitespace("yield /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_throw() {
+    let tokens = tokenize_no_whitespace("throw /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_extends() {
+    let tokens = tokenize_no_whitespace("class A extends /x/.constructor {}");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn test_regexp_after_default() {
+    let tokens = tokenize_no_whitespace("export default /x/");
+    assert_eq!(JsToken::RegexpLiteral("x".to_string(), "".to_string()), tokens[2]);
+}
+
+#[test]
+pub fn test_regexp_after_of() {
+    let tokens = tokenize_no_whitespace("for (const m of /a/g.exec(s)) {}");
+    assert_eq!(JsToken::RegexpLiteral("a".to_string(), "g".to_string()), tokens[5]);
+}
+
+#[test]
+pub fn test_div_after_of_identifier() {
+    let tokens = tokenize_no_whitespace("x = of / 2; for (of of of) of / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[13]);
+}
+
+#[test]
+pub fn test_div_after_of_property() {
+    let tokens = tokenize_no_whitespace("a.of / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+}
+
+#[test]
+pub fn test_regexp_after_debugger() {
+    let tokens = tokenize_no_whitespace("debugger\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_regexp_after_try() {
+    let tokens = tokenize_no_whitespace("try {} catch (e) {}\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[9]);
+}
+
+#[test]
+pub fn test_regexp_after_finally() {
+    let tokens = tokenize_no_whitespace("try {} finally {}\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[6]);
+}
+
+#[test]
+pub fn test_div_after_keyword_property() {
+    let tokens = tokenize_no_whitespace("a.typeof / b.return / c?.in / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[7]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[11]);
+}
+
+#[test]
+pub fn test_div_after_literal_keywords() {
+    let tokens = tokenize_no_whitespace("this / null / true / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[1]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[5]);
+}
+
+#[test]
+pub fn test_block_after_if_regexp() {
+    let tokens = tokenize_no_whitespace("if (x) { } /re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[6]);
+}
+
+#[test]
+pub fn test_function_expression_div() {
+    let tokens = tokenize_no_whitespace("var f = function(){} / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[8]);
+}
+
+#[test]
+pub fn test_named_function_expression_div() {
+    let tokens = tokenize_no_whitespace("x = async function* f(a, {b}){ return {} } / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[18]);
+}
+
+#[test]
+pub fn test_function_declaration_regexp() {
+    let tokens = tokenize_no_whitespace("function f(){}\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[6]);
+}
+
+#[test]
+pub fn test_class_expression_div() {
+    let tokens = tokenize_no_whitespace("x = class extends (B) { m() {} } / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[14]);
+}
+
+#[test]
+pub fn test_class_declaration_regexp() {
+    let tokens = tokenize_no_whitespace("class A { m() { return 1 } }\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[11]);
+}
+
+#[test]
+pub fn test_export_default_function_regexp() {
+    let tokens = tokenize_no_whitespace("export default function(){}\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[7]);
+    let tokens = tokenize_no_whitespace("export default async function* f(){}\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[10]);
+}
+
+#[test]
+pub fn test_export_default_class_regexp() {
+    let tokens = tokenize_no_whitespace("export default class { m() {} }\n/re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[10]);
+}
+
+#[test]
+pub fn test_export_default_expression_div() {
+    let tokens = tokenize_no_whitespace("export default (function(){}) / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[9]);
+}
+
+#[test]
+pub fn test_object_literal_div() {
+    let tokens = tokenize_no_whitespace("x = { a: {}, b() {} } / 2");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[14]);
+}
+
+#[test]
+pub fn test_case_block_regexp() {
+    let tokens = tokenize_no_whitespace("switch (x) { case a ? b : c: {} /re/.test(s) }");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[14]);
+}
+
+#[test]
+pub fn test_label_block_regexp() {
+    let tokens = tokenize_no_whitespace("foo: {} /re/.test(s)");
+    assert_eq!(JsToken::RegexpLiteral("re".to_string(), "".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn test_template_substitution_object_div() {
+    let tokens = tokenize_no_whitespace("`${ {} / 2 }`");
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[3]);
+}
+
 #[test]
 pub fn test_for_regexp() {
     let tokens = tokenize_no_whitespace("for({}/1;{}/1;{}/1)");
@@ -441,15 +980,110 @@ pub fn test_for_regexp() {
     assert_eq!(JsToken::Punctuation("/".to_string()), tokens[14]);
 }
 
-const JQUERY_SRC: &'static str = include_str!("jquery-1.12.4.js");
-const JQUERY_MIN_SRC: &'static str = include_str!("jquery-1.12.4.min.js");
+#[test]
+pub fn test_template_plain() {
+    let tokens = tokenize_no_whitespace("`a/b\\`c`/2");
+    assert_eq!(3, tokens.len());
+    assert_eq!(JsToken::TemplateLiteral("`a/b\\`c`".to_string()), tokens[0]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[1]);
+}
+
+#[test]
+pub fn test_template_substitutions() {
+    let tokens = tokenize_no_whitespace("`a${1}b${{}/x/}c`/2");
+    assert_eq!(11, tokens.len());
+    assert_eq!(JsToken::TemplateHead("`a${".to_string()), tokens[0]);
+    assert_eq!(JsToken::NumberLiteral("1".to_string()), tokens[1]);
+    assert_eq!(JsToken::TemplateMiddle("}b${".to_string()), tokens[2]);
+    assert_eq!(JsToken::Punctuation("{".to_string()), tokens[3]);
+    assert_eq!(JsToken::Punctuation("}".to_string()), tokens[4]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[5]);
+    assert_eq!(JsToken::TemplateTail("}c`".to_string()), tokens[8]);
+    assert_eq!(JsToken::Punctuation("/".to_string()), tokens[9]);
+}
+
+#[test]
+pub fn test_template_nested() {
+    let tokens = tokenize_no_whitespace("`a${`b${c}d`}e`");
+    assert_eq!(5, tokens.len());
+    assert_eq!(JsToken::TemplateHead("`a${".to_string()), tokens[0]);
+    assert_eq!(JsToken::TemplateHead("`b${".to_string()), tokens[1]);
+    assert_eq!(JsToken::Word("c".to_string()), tokens[2]);
+    assert_eq!(JsToken::TemplateTail("}d`".to_string()), tokens[3]);
+    assert_eq!(JsToken::TemplateTail("}e`".to_string()), tokens[4]);
+}
+
+#[test]
+pub fn test_template_regexp_after() {
+    let tokens = tokenize_no_whitespace("x = `${a}`;/q/");
+    assert_eq!(7, tokens.len());
+    assert_eq!(JsToken::RegexpLiteral("q".to_string(), "".to_string()), tokens[6]);
+}
+
+#[test]
+pub fn spans() {
+    let src = "a = '\u{1F600}';\r\n/x/";
+    let tokens: Vec<_> = tokenize_str_spanned(src).collect();
+    assert_eq!(8, tokens.len());
+    assert_eq!(Span { start: 0, end: 1, start_utf16: 0, end_utf16: 1 }, tokens[0].span);
+    assert_eq!(JsToken::StringLiteral("'\u{1F600}'".to_string()), tokens[4].kind);
+    assert_eq!(Span { start: 4, end: 10, start_utf16: 4, end_utf16: 8 }, tokens[4].span);
+    assert_eq!(Span { start: 11, end: 13, start_utf16: 9, end_utf16: 11 }, tokens[6].span);
+    assert_eq!(Span { start: 13, end: 16, start_utf16: 11, end_utf16: 14 }, tokens[7].span);
+}
+
+#[test]
+pub fn spans_jquery_contiguous() {
+    let mut offset = 0;
+    for token in tokenize_str_spanned(JQUERY_SRC) {
+        assert_eq!(offset, token.span.start);
+        offset = token.span.end;
+    }
+    assert_eq!(JQUERY_SRC.len(), offset);
+}
+
+#[test]
+pub fn borrowed_tokens() {
+    let src = "a = /x/g; // c\n/* */\\u0061b";
+    let tokens: Vec<_> = tokenize_str_ref(src).collect();
+    assert_eq!(JsTokenRef::RegexpLiteral("x", "g"), tokens[4]);
+    assert_eq!(JsTokenRef::LineComment("// c"), tokens[7]);
+    assert_eq!(JsTokenRef::MultilineComment("/* */"), tokens[9]);
+
+    // slices of the source, decoded only when there are escapes
+    match tokens[0] {
+        JsTokenRef::Word(w) => assert_eq!(src.as_ptr(), w.as_ptr()),
+        ref t => panic!("{:?}", t),
+    }
+    assert!(matches!(tokens[0].identifier_name(), Some(Cow::Borrowed("a"))));
+    assert!(matches!(tokens[10].identifier_name(), Some(Cow::Owned(ref x)) if x == "ab"));
+}
+
+#[test]
+pub fn borrowed_tokens_jquery() {
+    let owned = tokenize(JQUERY_SRC);
+    let borrowed: Vec<JsToken> = tokenize_str_ref(JQUERY_SRC).map(JsToken::from).collect();
+    assert_eq!(owned, borrowed);
+    for token in &owned {
+        assert_eq!(*token, JsToken::from(token.as_token_ref()));
+    }
+}
+
+#[test]
+pub fn comment_at_end() {
+    assert_eq!(vec![JsToken::LineComment("//".to_string())], tokenize("//"));
+    assert_eq!(vec![JsToken::MultilineComment("/**".to_string())], tokenize("/**"));
+}
+
+const JQUERY_SRC: &str = include_str!("jquery-1.12.4.js");
+const JQUERY_MIN_SRC: &str = include_str!("jquery-1.12.4.min.js");
 
 #[test]
 pub fn lex_jquery() {
     let tokens = tokenize(JQUERY_SRC);
     for token in &tokens {
-        if let &JsToken::Unknown(_) = token {
-            assert!(false, "Found unknown token: {:?}", token);
+        if let JsToken::Unknown(_) = *token {
+            panic!("Found unknown token: {:?}", token);
         }
     }
 }
@@ -459,8 +1093,8 @@ pub fn lex_jquery() {
 pub fn lex_jquery_min() {
     let tokens = tokenize(JQUERY_MIN_SRC);
     for token in &tokens {
-        if let &JsToken::Unknown(_) = token {
-            assert!(false, "Found unknown token: {:?}", token);
+        if let JsToken::Unknown(_) = *token {
+            panic!("Found unknown token: {:?}", token);
         }
     }
 }
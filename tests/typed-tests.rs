@@ -0,0 +1,56 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::typed::*;
+
+fn typed_kinds(src: &str) -> Vec<JsToken> {
+    tokenize_str(src).filter(|t| !matches!(*t, JsToken::Whitespace(_))).collect()
+}
+
+#[test]
+pub fn keywords_and_identifiers() {
+    let tokens = typed_kinds("return x; let y = this");
+    assert_eq!(TypedToken::Keyword(Keyword::Return), tokens[0].typed());
+    assert_eq!(TypedToken::Identifier { name: "x".into(), contextual: None }, tokens[1].typed());
+    assert_eq!(TypedToken::Punctuator(Punctuator::Semicolon), tokens[2].typed());
+    assert_eq!(TypedToken::Identifier { name: "let".into(), contextual: Some(ContextualKeyword::Let) }, tokens[3].typed());
+    assert_eq!(Some(Keyword::This), tokens[6].keyword());
+    assert_eq!(None, tokens[1].keyword());
+}
+
+#[test]
+pub fn escaped_keyword() {
+    let tokens = typed_kinds("\\u0069f as\\u{79}nc");
+    assert_eq!(Some(Keyword::If), tokens[0].keyword());
+    assert_eq!(Some(ContextualKeyword::Async), tokens[1].contextual_keyword());
+    // the raw text is still there
+    assert_eq!(JsToken::Word("\\u0069f".to_string()), tokens[0]);
+}
+
+#[test]
+pub fn punctuators() {
+    let tokens = typed_kinds(">>>= ?. ?? => ... ** {");
+    let expected = vec![
+        Punctuator::UShrAssign,
+        Punctuator::QuestionDot,
+        Punctuator::QuestionQuestion,
+        Punctuator::Arrow,
+        Punctuator::Ellipsis,
+        Punctuator::StarStar,
+        Punctuator::LBrace,
+    ];
+    assert_eq!(expected, tokens.iter().map(|t| t.punctuator().unwrap()).collect::<Vec<_>>());
+    for p in expected {
+        assert_eq!(Some(p), Punctuator::from_text(p.as_str()));
+    }
+}
+
+#[test]
+pub fn other_tokens_pass_through() {
+    let tokens = typed_kinds("'a' 1");
+    assert_eq!(TypedToken::Other(&tokens[0]), tokens[0].typed());
+    assert_eq!(None, tokens[1].punctuator());
+    assert_eq!("instanceof", Keyword::Instanceof.as_str());
+    assert_eq!(Some(Keyword::Instanceof), Keyword::from_name("instanceof"));
+    assert_eq!(None, Keyword::from_name("let"));
+}
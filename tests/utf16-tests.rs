@@ -0,0 +1,82 @@
+extern crate js_lex_rs;
+
+use js_lex_rs::*;
+use js_lex_rs::diagnostic::*;
+
+fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+/// `s` with each `@` replaced by a lone high surrogate.
+fn with_lone_surrogates(s: &str) -> Vec<u16> {
+    s.encode_utf16().map(|c| if c == '@' as u16 { 0xd800 } else { c }).collect()
+}
+
+#[test]
+pub fn same_as_tokenize() {
+    let src = include_str!("jquery-1.12.4.js");
+    let units = utf16(src);
+    let tokens: Vec<JsToken> = tokenize_utf16(&units).map(|t| t.kind).collect();
+    assert_eq!(tokenize(src), tokens);
+}
+
+#[test]
+pub fn spans_in_code_units() {
+    let units = utf16("x = '\u{1F600}' + y");
+    let tokens: Vec<_> = tokenize_utf16(&units).collect();
+    assert_eq!(JsToken::StringLiteral("'\u{1F600}'".to_string()), tokens[4].kind);
+    assert_eq!((4, 8), (tokens[4].span.start_utf16, tokens[4].span.end_utf16));
+    assert_eq!(&units[4..8], tokens[4].text);
+    assert_eq!((11, 12), (tokens[8].span.start_utf16, tokens[8].span.end_utf16));
+}
+
+#[test]
+pub fn lone_surrogates_kept() {
+    let units = with_lone_surrogates("'a@' /*@*/ `@${x}@` // @");
+    let mut iter = tokenize_utf16(&units);
+    let tokens: Vec<_> = iter.by_ref().collect();
+    assert!(iter.diagnostics().is_empty());
+    assert_eq!(JsToken::StringLiteral("'a\u{fffd}'".to_string()), tokens[0].kind);
+    assert_eq!(&[0x27, 0x61, 0xd800, 0x27], tokens[0].text);
+    let joined: Vec<u16> = tokens.iter().flat_map(|t| t.text.iter().cloned()).collect();
+    assert_eq!(units, joined);
+}
+
+#[test]
+pub fn lone_surrogates_reported() {
+    let mut units = utf16("a \u{fffd} /b/ c");
+    units[2] = 0xdc00;
+    let mut iter = tokenize_utf16(&units);
+    let tokens: Vec<_> = iter.by_ref().collect();
+    assert_eq!(JsToken::Unknown("\u{fffd}".to_string()), tokens[2].kind);
+    assert_eq!(&[0xdc00], tokens[2].text);
+    let diagnostics = iter.take_diagnostics();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(DiagnosticCode::LoneSurrogate, diagnostics[0].code);
+    assert_eq!("lone surrogate U+DC00 outside of a string, comment or template", diagnostics[0].message);
+    assert_eq!((2, 3), (diagnostics[0].span.start_utf16, diagnostics[0].span.end_utf16));
+}
+
+#[test]
+pub fn lone_surrogates_in_regexp() {
+    let units = with_lone_surrogates("x = /a@b@/z");
+    let mut iter = tokenize_utf16(&units);
+    let tokens: Vec<_> = iter.by_ref().collect();
+    assert_eq!(JsToken::RegexpLiteral("a\u{fffd}b\u{fffd}".to_string(), "z".to_string()), tokens[4].kind);
+    let diagnostics: Vec<_> = iter.diagnostics().iter()
+        .map(|d| (d.code, d.span.start_utf16))
+        .collect();
+    assert_eq!(vec![
+        (DiagnosticCode::LoneSurrogate, 6),
+        (DiagnosticCode::LoneSurrogate, 8),
+        (DiagnosticCode::InvalidRegexpFlags, 10),
+    ], diagnostics);
+}
+
+#[test]
+pub fn replacement_character_is_unexpected() {
+    let units = utf16("\u{fffd}");
+    let mut iter = tokenize_utf16(&units);
+    iter.by_ref().for_each(drop);
+    assert_eq!(DiagnosticCode::UnexpectedCharacter, iter.diagnostics()[0].code);
+}